@@ -0,0 +1,135 @@
+use std::{fmt::Display, path::PathBuf};
+
+use color_eyre::eyre::Context;
+
+use crate::{EyreResult, Part};
+
+/// Expected answers, stored as `<dir>/<year>/day<N>/part<M>`
+pub(crate) struct AnswerStore {
+    dir: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Pass,
+    Fail { expected: String },
+    Missing,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Fail { .. } => write!(f, "FAIL"),
+            Verdict::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+/// Answers are compared without their trailing newlines, so that files edited by hand still match
+fn normalize(answer: &str) -> &str {
+    answer.trim_end_matches(['\n', '\r'])
+}
+
+impl AnswerStore {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, year: usize, day: usize, part: Part) -> PathBuf {
+        let mut path = self.dir.clone();
+        path.push(year.to_string());
+        path.push(format!("day{}", day));
+        path.push(format!("part{}", part));
+        path
+    }
+
+    pub(crate) fn load(&self, year: usize, day: usize, part: Part) -> EyreResult<Option<String>> {
+        let path = self.path(year, day, part);
+        if !path.exists() {
+            return Ok(None);
+        }
+        std::fs::read_to_string(&path)
+            .map(Some)
+            .with_context(|| format!("Could not read the answer at {:?}", path))
+    }
+
+    pub(crate) fn record(
+        &self,
+        year: usize,
+        day: usize,
+        part: Part,
+        answer: &str,
+    ) -> EyreResult<()> {
+        let path = self.path(year, day, part);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create the directory {:?}", parent))?;
+        }
+        std::fs::write(&path, format!("{}\n", normalize(answer)))
+            .with_context(|| format!("Could not write the answer to {:?}", path))
+    }
+
+    pub(crate) fn check(
+        &self,
+        year: usize,
+        day: usize,
+        part: Part,
+        answer: &str,
+    ) -> EyreResult<Verdict> {
+        Ok(match self.load(year, day, part)? {
+            None => Verdict::Missing,
+            Some(expected) if normalize(&expected) == normalize(answer) => Verdict::Pass,
+            Some(expected) => Verdict::Fail {
+                expected: normalize(&expected).to_string(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnswerStore, Verdict};
+    use crate::Part;
+
+    #[test]
+    fn record_then_check() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("aoc-answers-test-{}", std::process::id()));
+        let store = AnswerStore::new(dir.clone());
+
+        assert_eq!(
+            store.check(2021, 4, Part::Part1, "12").unwrap(),
+            Verdict::Missing
+        );
+        store.record(2021, 4, Part::Part1, "12").unwrap();
+        store.record(2021, 13, Part::Part2, "# #\n ##\n").unwrap();
+        assert_eq!(
+            store.check(2021, 4, Part::Part1, "12").unwrap(),
+            Verdict::Pass
+        );
+        assert_eq!(
+            store.check(2021, 4, Part::Part1, "13").unwrap(),
+            Verdict::Fail {
+                expected: "12".into()
+            }
+        );
+        assert_eq!(
+            store.check(2021, 13, Part::Part2, "# #\n ##").unwrap(),
+            Verdict::Pass
+        );
+        // The answers of a day are kept apart for each year
+        assert_eq!(
+            store.check(2022, 4, Part::Part1, "12").unwrap(),
+            Verdict::Missing
+        );
+        store.record(2022, 4, Part::Part1, "7").unwrap();
+        assert_eq!(
+            store.check(2021, 4, Part::Part1, "12").unwrap(),
+            Verdict::Pass
+        );
+        assert!(dir.join("2022/day4/part1").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,294 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use color_eyre::eyre::{self, Context};
+
+use crate::{
+    harness::{self, Align, Outcome},
+    EyreResult, Part,
+};
+
+/// Summary statistics over a set of timing samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Stats {
+    pub(crate) min: Duration,
+    pub(crate) mean: Duration,
+    pub(crate) median: Duration,
+    pub(crate) p95: Duration,
+    pub(crate) stddev: Duration,
+}
+
+impl Stats {
+    pub(crate) fn from_samples(samples: &mut [Duration]) -> Stats {
+        assert!(!samples.is_empty(), "no samples to compute statistics on");
+        samples.sort_unstable();
+
+        let n = samples.len();
+        let mean = samples.iter().sum::<Duration>() / n as u32;
+        let median = if n.is_multiple_of(2) {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        } else {
+            samples[n / 2]
+        };
+        // Nearest rank percentile
+        let p95 = samples[(n * 95).div_ceil(100).max(1) - 1];
+        let variance = samples
+            .iter()
+            .map(|s| (s.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        Stats {
+            min: samples[0],
+            mean,
+            median,
+            p95,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+pub(crate) struct BenchResult {
+    pub(crate) year: usize,
+    pub(crate) day: usize,
+    pub(crate) part: Part,
+    pub(crate) parse: Stats,
+    /// Step shared by both parts, for the days that have one
+    pub(crate) shared: Option<Stats>,
+    pub(crate) solve: Stats,
+}
+
+/// Runs `f` `warmup` times without recording, then `iterations` times collecting the timings
+pub(crate) fn bench<F>(
+    year: usize,
+    day: usize,
+    part: Part,
+    iterations: usize,
+    warmup: usize,
+    mut f: F,
+) -> EyreResult<BenchResult>
+where
+    F: FnMut() -> EyreResult<Outcome>,
+{
+    if iterations == 0 {
+        eyre::bail!("Can't benchmark with 0 iterations");
+    }
+
+    for _ in 0..warmup {
+        f()?;
+    }
+
+    let mut parse = Vec::with_capacity(iterations);
+    let mut shared = Vec::new();
+    let mut solve = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let outcome = f()?;
+        parse.push(outcome.timings.parse.unwrap_or_default());
+        shared.extend(outcome.timings.inter);
+        solve.push(outcome.timings.part);
+    }
+
+    Ok(BenchResult {
+        year,
+        day,
+        part,
+        parse: Stats::from_samples(&mut parse),
+        shared: (!shared.is_empty()).then(|| Stats::from_samples(&mut shared)),
+        solve: Stats::from_samples(&mut solve),
+    })
+}
+
+/// Median timings of each phase of a benchmark
+struct Medians {
+    parse: Duration,
+    shared: Option<Duration>,
+    solve: Duration,
+}
+
+/// Median timings of a previous benchmark, used to detect regressions
+pub(crate) struct Baseline {
+    medians: HashMap<(usize, usize, Part), Medians>,
+}
+
+impl Baseline {
+    pub(crate) fn load(path: &Path) -> EyreResult<Baseline> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read the baseline at {:?}", path))?;
+
+        let medians = content
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .map(|line| -> EyreResult<_> {
+                let fields: Vec<_> = line.split_whitespace().collect();
+                let (year, day, part, parse, shared, solve) = match fields[..] {
+                    [year, day, part, parse, shared, solve] => {
+                        (year, day, part, parse, shared, solve)
+                    }
+                    // Baselines saved before the shared step was measured
+                    [year, day, part, parse, solve] => (year, day, part, parse, "-", solve),
+                    _ => eyre::bail!("Malformed baseline line: {:?}", line),
+                };
+                Ok((
+                    (year.parse()?, day.parse()?, part.parse()?),
+                    Medians {
+                        parse: Duration::from_nanos(parse.parse()?),
+                        shared: match shared {
+                            "-" => None,
+                            shared => Some(Duration::from_nanos(shared.parse()?)),
+                        },
+                        solve: Duration::from_nanos(solve.parse()?),
+                    },
+                ))
+            })
+            .collect::<EyreResult<_>>()?;
+
+        Ok(Baseline { medians })
+    }
+
+    pub(crate) fn save(path: &Path, results: &[BenchResult]) -> EyreResult<()> {
+        let mut out =
+            String::from("# year day part parse_median_ns shared_median_ns solve_median_ns\n");
+        for result in results {
+            out += &format!(
+                "{} {} {} {} {} {}\n",
+                result.year,
+                result.day,
+                result.part,
+                result.parse.median.as_nanos(),
+                match result.shared {
+                    Some(shared) => shared.median.as_nanos().to_string(),
+                    None => "-".into(),
+                },
+                result.solve.median.as_nanos()
+            );
+        }
+        std::fs::write(path, out)
+            .with_context(|| format!("Could not write the baseline to {:?}", path))
+    }
+}
+
+/// Relative change of `current` compared to `baseline`, in percent
+fn change(baseline: Duration, current: Duration) -> f64 {
+    (current.as_secs_f64() / baseline.as_secs_f64() - 1.) * 100.
+}
+
+/// Renders the benchmark results, returning the number of regressions above `threshold` percent
+pub(crate) fn render(
+    results: &[BenchResult],
+    baseline: Option<&Baseline>,
+    threshold: f64,
+) -> (String, usize) {
+    let mut regressions = 0;
+    let mut rows = Vec::new();
+    for result in results {
+        let base = baseline.and_then(|b| b.medians.get(&(result.year, result.day, result.part)));
+        let mut phases = vec![("parse", &result.parse, base.map(|base| base.parse))];
+        if let Some(shared) = &result.shared {
+            phases.push(("shared", shared, base.and_then(|base| base.shared)));
+        }
+        phases.push(("solve", &result.solve, base.map(|base| base.solve)));
+        for (phase, stats, base) in phases {
+            let mut row = vec![
+                result.day.to_string(),
+                result.part.to_string(),
+                phase.to_string(),
+                format!("{:.2?}", stats.min),
+                format!("{:.2?}", stats.mean),
+                format!("{:.2?}", stats.median),
+                format!("{:.2?}", stats.p95),
+                format!("{:.2?}", stats.stddev),
+            ];
+            if baseline.is_some() {
+                row.push(match base {
+                    None => "-".into(),
+                    Some(base) => {
+                        let change = change(base, stats.median);
+                        if change > threshold {
+                            regressions += 1;
+                            format!("{:+.1}% REGRESSION", change)
+                        } else {
+                            format!("{:+.1}%", change)
+                        }
+                    }
+                });
+            }
+            rows.push(row);
+        }
+    }
+
+    let mut header = vec![
+        ("Day", Align::Right),
+        ("Part", Align::Right),
+        ("Phase", Align::Left),
+        ("Min", Align::Right),
+        ("Mean", Align::Right),
+        ("Median", Align::Right),
+        ("p95", Align::Right),
+        ("Std dev", Align::Right),
+    ];
+    if baseline.is_some() {
+        header.push(("vs baseline", Align::Left));
+    }
+
+    (harness::render_table(&header, &rows), regressions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render, Baseline, BenchResult, Stats};
+    use crate::Part;
+    use std::time::Duration;
+
+    #[test]
+    fn stats() {
+        let mut samples: Vec<_> = [5, 1, 3, 2, 4]
+            .into_iter()
+            .map(Duration::from_millis)
+            .collect();
+        let stats = Stats::from_samples(&mut samples);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.mean, Duration::from_millis(3));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.p95, Duration::from_millis(5));
+        assert_eq!(stats.stddev.as_micros(), 1414);
+
+        let mut samples: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        let stats = Stats::from_samples(&mut samples);
+        assert_eq!(stats.median, Duration::from_micros(50_500));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+    }
+
+    #[test]
+    fn baseline() {
+        let result = |year, millis| {
+            let stats = Stats::from_samples(&mut [Duration::from_millis(millis)]);
+            BenchResult {
+                year,
+                day: 1,
+                part: Part::Part1,
+                parse: stats,
+                shared: Some(stats),
+                solve: stats,
+            }
+        };
+        let path = std::env::temp_dir().join(format!("aoc-baseline-{}", std::process::id()));
+        Baseline::save(&path, &[result(2021, 10), result(2022, 100)]).unwrap();
+        let baseline = Baseline::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The same day of another year is compared with its own timings
+        let (_, regressions) = render(&[result(2022, 50)], Some(&baseline), 10.);
+        assert_eq!(regressions, 0);
+        let (_, regressions) = render(&[result(2021, 50)], Some(&baseline), 10.);
+        assert_eq!(regressions, 3);
+
+        // A baseline without the shared step only compares the other phases
+        std::fs::write(&path, "2021 1 1 10000000 10000000\n").unwrap();
+        let baseline = Baseline::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let (table, regressions) = render(&[result(2021, 50)], Some(&baseline), 10.);
+        assert_eq!(regressions, 2);
+        assert!(table
+            .lines()
+            .any(|line| line.contains("shared") && line.ends_with('-')));
+    }
+}
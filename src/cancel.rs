@@ -0,0 +1,83 @@
+//! Cooperative cancellation of the solvers
+//!
+//! A run is given a [`Token`] with [`scope`], and its long loops poll [`check`] to stop once the
+//! token is cancelled, such as when the run timed out.
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Shared flag telling a run to stop
+#[derive(Debug, Clone, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `token` as the token of the current thread
+pub fn scope<O>(token: Token, f: impl FnOnce() -> O) -> O {
+    let previous = CURRENT.with(|current| current.replace(Some(token)));
+    let out = f();
+    CURRENT.with(|current| current.replace(previous));
+    out
+}
+
+/// Error of a run that was told to stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The run was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Fails once the token of the current thread is cancelled, it never does outside of [`scope`]
+pub fn check() -> Result<(), Cancelled> {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(token) if token.is_cancelled() => Err(Cancelled),
+        _ => Ok(()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, scope, Cancelled, Token};
+
+    #[test]
+    fn scoped() {
+        let token = Token::new();
+        assert_eq!(check(), Ok(()));
+        scope(token.clone(), || {
+            assert_eq!(check(), Ok(()));
+            token.cancel();
+            assert_eq!(check(), Err(Cancelled));
+            // An inner run has its own token
+            scope(Token::new(), || assert_eq!(check(), Ok(())));
+            assert_eq!(check(), Err(Cancelled));
+        });
+        assert_eq!(check(), Ok(()));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{self, Context};
+use serde::Deserialize;
+
+use crate::{output::Format, EyreResult};
+
+/// Name of the configuration file of a checkout, looked up in the current directory and its
+/// parents
+pub(crate) const PROJECT_FILE: &str = "aoc.toml";
+
+/// Settings that can be given to the runs together, overridden by the command line
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    pub(crate) session: Option<String>,
+    pub(crate) year: Option<usize>,
+    /// Relative to the directory of the file holding the profile
+    pub(crate) dl_dir: Option<PathBuf>,
+    pub(crate) format: Option<Format>,
+    pub(crate) timings: Option<bool>,
+}
+
+impl Profile {
+    /// Settings of `self`, completed by the ones of `other`
+    fn or(self, other: Profile) -> Profile {
+        Profile {
+            session: self.session.or(other.session),
+            year: self.year.or(other.year),
+            dl_dir: self.dl_dir.or(other.dl_dir),
+            format: self.format.or(other.format),
+            timings: self.timings.or(other.timings),
+        }
+    }
+}
+
+/// Profiles of a configuration file, such as:
+///
+/// ```toml
+/// default = "alice"
+///
+/// [profiles.alice]
+/// session = "53616c7465645f5f..."
+/// year = 2021
+/// dl_dir = "inputs/alice"
+/// format = "text"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Profile used when none is selected
+    default: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    fn parse(content: &str, dir: &Path) -> EyreResult<Config> {
+        let mut config: Config = toml::from_str(content)?;
+        for profile in config.profiles.values_mut() {
+            profile.dl_dir = profile.dl_dir.take().map(|path| dir.join(path));
+        }
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> EyreResult<Option<Config>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Could not read {:?}", path)),
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::parse(&content, dir)
+            .with_context(|| format!("Invalid configuration in {:?}", path))
+            .map(Some)
+    }
+
+    /// Configuration of `self`, where the profiles missing settings take them from `other`
+    fn or(mut self, other: Config) -> Config {
+        for (name, profile) in other.profiles {
+            let merged = match self.profiles.remove(&name) {
+                Some(ours) => ours.or(profile),
+                None => profile,
+            };
+            self.profiles.insert(name, merged);
+        }
+        Config {
+            default: self.default.or(other.default),
+            profiles: self.profiles,
+        }
+    }
+
+    /// Loads the configuration of the user, `$XDG_CONFIG_HOME/aoc/config.toml`, and the one of the
+    /// current checkout, which takes precedence
+    pub(crate) fn load() -> EyreResult<Config> {
+        let mut config = Config::default();
+        if let Some(path) = user_file() {
+            config = Self::read(&path)?.unwrap_or_default();
+        }
+        let project = std::env::current_dir()?
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file());
+        if let Some(path) = project {
+            if let Some(project) = Self::read(&path)? {
+                config = project.or(config);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The profile named `name`, or the default one if there is one
+    pub(crate) fn profile(&self, name: Option<&str>) -> EyreResult<Option<&Profile>> {
+        match name.or(self.default.as_deref()) {
+            None => Ok(None),
+            Some(name) => match self.profiles.get(name) {
+                Some(profile) => Ok(Some(profile)),
+                None if self.profiles.is_empty() => eyre::bail!("No profile is configured"),
+                None => eyre::bail!(
+                    "No profile named {:?}, the configured ones are: {}",
+                    name,
+                    self.profiles
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+        }
+    }
+}
+
+fn user_file() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("aoc").join("config.toml"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Config, Profile};
+    use crate::output::Format;
+
+    #[test]
+    fn profiles() {
+        let user = Config::parse(
+            r#"
+            default = "alice"
+
+            [profiles.alice]
+            session = "secret"
+            year = 2020
+            dl_dir = "/inputs/alice"
+
+            [profiles.bob]
+            session = "other"
+            "#,
+            Path::new("/home/alice/.config/aoc"),
+        )
+        .unwrap();
+        let project = Config::parse(
+            r#"
+            [profiles.alice]
+            year = 2021
+            format = "csv"
+
+            [profiles.bob]
+            dl_dir = "inputs/bob"
+            timings = false
+            "#,
+            Path::new("/checkout"),
+        )
+        .unwrap();
+        let config = project.or(user);
+
+        assert_eq!(
+            config.profile(None).unwrap(),
+            Some(&Profile {
+                session: Some("secret".into()),
+                year: Some(2021),
+                dl_dir: Some("/inputs/alice".into()),
+                format: Some(Format::Csv),
+                timings: None,
+            })
+        );
+        assert_eq!(
+            config.profile(Some("bob")).unwrap(),
+            Some(&Profile {
+                session: Some("other".into()),
+                dl_dir: Some("/checkout/inputs/bob".into()),
+                timings: Some(false),
+                ..Default::default()
+            })
+        );
+        assert!(config.profile(Some("carol")).is_err());
+        assert_eq!(Config::default().profile(None).unwrap(), None);
+
+        assert!(Config::parse("[profiles.alice]\nsesion = \"typo\"", Path::new("")).is_err());
+    }
+}
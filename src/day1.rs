@@ -1,19 +1,30 @@
-use crate::{EyreResult, day};
+use crate::{forward_solution, parse, solution::Solution, EyreResult};
 
-day! {
-    parser,
-    part1 => "The depth increased {} times",
-    part2 => "The depth increased {} times with windows",
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: usize = 1;
+    const TITLE: &'static str = "Sonar Sweep";
+    const MESSAGES: [&'static str; 2] = [
+        "The depth increased {} times",
+        "The depth increased {} times with windows",
+    ];
+
+    type Parsed<'i> = Vec<u64>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Vec<u64>> {
+pub fn parser(input: &str) -> EyreResult<Vec<u64>> {
     input
         .lines()
-        .map(|l| l.trim().parse().map_err(Into::into))
+        .map(|l| Ok(parse::token(input, l.trim())?))
         .collect()
 }
 
-pub(crate) fn part1(depths: Vec<u64>) -> EyreResult<usize> {
+pub fn part1(depths: Vec<u64>) -> EyreResult<usize> {
     Ok(depths
         .iter()
         .zip(depths.iter().skip(1))
@@ -21,7 +32,7 @@ pub(crate) fn part1(depths: Vec<u64>) -> EyreResult<usize> {
         .count())
 }
 
-pub(crate) fn part2(depths: Vec<u64>) -> EyreResult<usize> {
+pub fn part2(depths: Vec<u64>) -> EyreResult<usize> {
     let sums = || {
         depths
             .iter()
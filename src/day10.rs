@@ -1,33 +1,44 @@
-use crate::{day, EyreResult};
+use crate::{forward_solution, parse::ParseError, solution::Solution, EyreResult};
 
-type Parsed = Vec<Vec<i8>>;
+pub type Parsed = Vec<Vec<i8>>;
 
-day! {
-    parser,
-    part1 => "Syntax error score is {}",
-    part2 => "Middle auto complete is {}",
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: usize = 10;
+    const TITLE: &'static str = "Syntax Scoring";
+    const MESSAGES: [&'static str; 2] = ["Syntax error score is {}", "Middle auto complete is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input
         .lines()
         .map(|l| {
-            l.trim()
-                .bytes()
-                .map(|v| match v {
-                    b'(' => Ok(1),
-                    b')' => Ok(-1),
-                    b'[' => Ok(2),
-                    b']' => Ok(-2),
-                    b'{' => Ok(3),
-                    b'}' => Ok(-3),
-                    b'<' => Ok(4),
-                    b'>' => Ok(-4),
-                    _ => {
-                        color_eyre::eyre::bail!("Invalid character: {:?}", char::from_u32(v.into()))
-                    }
+            let l = l.trim();
+            l.char_indices()
+                .map(|(i, c)| match c {
+                    '(' => Ok(1),
+                    ')' => Ok(-1),
+                    '[' => Ok(2),
+                    ']' => Ok(-2),
+                    '{' => Ok(3),
+                    '}' => Ok(-3),
+                    '<' => Ok(4),
+                    '>' => Ok(-4),
+                    _ => Err(ParseError::new(
+                        input,
+                        &l[i..i + c.len_utf8()],
+                        "Expected a bracket",
+                    )),
                 })
-                .collect()
+                .collect::<Result<_, _>>()
+                .map_err(Into::into)
         })
         .collect()
 }
@@ -38,8 +49,8 @@ fn is_corrupted(line: &[i8], stack: &mut Vec<i8>) -> Option<i8> {
         if c > 0 {
             stack.push(c);
         } else {
-            let poped = stack.pop().unwrap();
-            if poped != -c {
+            // A closing bracket with nothing open is corrupted too
+            if stack.pop() != Some(-c) {
                 return Some(-c);
             }
         }
@@ -47,7 +58,7 @@ fn is_corrupted(line: &[i8], stack: &mut Vec<i8>) -> Option<i8> {
     None
 }
 
-pub(crate) fn part1(input: Parsed) -> EyreResult<usize> {
+pub fn part1(input: Parsed) -> EyreResult<usize> {
     let mut stack = Vec::new();
     let mut syntax_error = 0;
 
@@ -67,7 +78,7 @@ pub(crate) fn part1(input: Parsed) -> EyreResult<usize> {
     Ok(syntax_error)
 }
 
-pub(crate) fn part2(input: Parsed) -> EyreResult<u64> {
+pub fn part2(input: Parsed) -> EyreResult<u64> {
     let mut stack = Vec::new();
     let mut stack2 = Vec::new();
     let mut scores: Vec<_> = input
@@ -89,5 +100,8 @@ pub(crate) fn part2(input: Parsed) -> EyreResult<u64> {
         })
         .collect();
     scores.sort();
-    Ok(scores[scores.len() / 2])
+    scores
+        .get(scores.len() / 2)
+        .copied()
+        .ok_or_else(|| color_eyre::eyre::eyre!("No line is incomplete"))
 }
@@ -1,37 +1,59 @@
-use crate::{day, EyreResult};
-use arrayvec::ArrayVec;
-
-day! {
-    parser,
-    part1 => "There where {} flashes",
-    part2 => "Synchronizes after {} steps",
+use crate::{
+    cancel, forward_solution,
+    params::{AnyParam, Param},
+    parse::{self, ParseError},
+    solution::Solution,
+    visualize::{self, Cell, Frame},
+    EyreResult, Part,
+};
+use ansi_term::{Colour, Style};
+
+pub struct Day11;
+
+pub const STEPS: Param<usize> =
+    Param::new("steps", "Steps during which the flashes are counted", 100);
+
+impl Solution for Day11 {
+    const DAY: usize = 11;
+    const TITLE: &'static str = "Dumbo Octopus";
+    const MESSAGES: [&'static str; 2] = ["There where {} flashes", "Synchronizes after {} steps"];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&STEPS];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
 #[derive(Debug, Copy, Clone)]
-pub(crate) struct Octopus {
+pub struct Octopus {
     energy: u8,
     flashed: bool,
 }
 
-type Parsed = [[Octopus; 10]; 10];
+pub type Parsed = [[Octopus; 10]; 10];
+
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (width, digits) = parse::digit_grid(input)?;
+    let height = digits.len() / width;
+    if width != 10 || height != 10 {
+        return Err(ParseError::new(
+            input,
+            input,
+            format!("Expected 10x10 octopuses, got {}x{}", width, height),
+        )
+        .into());
+    }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    input
-        .lines()
-        .map(|line| {
-            line.as_bytes()
-                .iter()
-                .map(|b| Octopus {
-                    energy: b - b'0',
-                    flashed: false,
-                })
-                .collect::<ArrayVec<_, 10>>()
-                .into_inner()
-                .map_err(|_| color_eyre::eyre::eyre!("Line is not of len 10"))
-        })
-        .collect::<EyreResult<ArrayVec<_, 10>>>()?
-        .into_inner()
-        .map_err(|_| color_eyre::eyre::eyre!("There are not 10 lines"))
+    let mut octopuses = [[Octopus {
+        energy: 0,
+        flashed: false,
+    }; 10]; 10];
+    for (octopus, energy) in octopuses.iter_mut().flatten().zip(digits) {
+        octopus.energy = energy;
+    }
+    Ok(octopuses)
 }
 
 impl Octopus {
@@ -86,16 +108,37 @@ fn step(input: &mut Parsed) -> usize {
         })
     });
 
+    visualize::show(|| frame(input, flashes));
     flashes
 }
 
-pub(crate) fn part1(mut input: Parsed) -> EyreResult<usize> {
-    Ok((0..100).map(|_| step(&mut input)).sum())
+/// The energy of the octopuses, the ones that just flashed are highlighted
+fn frame(input: &Parsed, flashes: usize) -> Frame {
+    let mut frame = Frame::new(10, 10);
+    for (y, line) in input.iter().enumerate() {
+        for (x, octopus) in line.iter().enumerate() {
+            let glyph = char::from(b'0' + octopus.energy);
+            frame.set(
+                x,
+                y,
+                match octopus.energy {
+                    0 => Cell::styled(glyph, Colour::Yellow.bold()),
+                    _ => Cell::styled(glyph, Style::new().dimmed()),
+                },
+            );
+        }
+    }
+    frame.caption(format!("{} flashes", flashes))
+}
+
+pub fn part1(mut input: Parsed) -> EyreResult<usize> {
+    Ok((0..STEPS.get(Part::Part1)?).map(|_| step(&mut input)).sum())
 }
 
-pub(crate) fn part2(mut input: Parsed) -> EyreResult<usize> {
+pub fn part2(mut input: Parsed) -> EyreResult<usize> {
     let mut i = 0;
     loop {
+        cancel::check()?;
         i += 1;
         if step(&mut input) == 10 * 10 {
             break Ok(i);
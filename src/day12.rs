@@ -1,37 +1,62 @@
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    trace, EyreResult,
+};
 use petgraph::graph::{NodeIndex, UnGraph};
 use std::collections::HashMap;
 
-day! {
-    parser,
-    part1 => "There are {} paths",
-    part2 => "There are {} paths",
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: usize = 12;
+    const TITLE: &'static str = "Passage Pathing";
+    const MESSAGES: [&'static str; 2] = ["There are {} paths", "There are {} paths"];
+
+    type Parsed<'i> = Parsed<'i>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
 #[derive(Clone, Copy, Debug, Default)]
-pub(crate) struct Cave<'a> {
+pub struct Cave<'a> {
     name: &'a str,
     small: bool,
 }
 
-type Parsed<'a> = (HashMap<&'a str, NodeIndex>, UnGraph<Cave<'a>, ()>);
+pub type Parsed<'a> = (HashMap<&'a str, NodeIndex>, UnGraph<Cave<'a>, ()>);
+
+fn parse_cave<'i>(input: &str, name: &'i str) -> Result<Cave<'i>, ParseError> {
+    let small = match name {
+        "" => None,
+        _ if name.chars().all(|c| c.is_ascii_lowercase()) => Some(true),
+        _ if name.chars().all(|c| c.is_ascii_uppercase()) => Some(false),
+        _ => None,
+    };
+    small.map(|small| Cave { name, small }).ok_or_else(|| {
+        ParseError::new(
+            input,
+            name,
+            "Caves are named in either lowercase or uppercase",
+        )
+    })
+}
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed<'_>> {
+pub fn parser(input: &str) -> EyreResult<Parsed<'_>> {
     let mut nodes = HashMap::new();
     let mut graph = UnGraph::new_undirected();
     let edges: Vec<_> = input
         .lines()
         .map(|line| -> EyreResult<_> {
-            let (start, end) =
-                split2(line, "-").ok_or(color_eyre::eyre::eyre!("Invalid line: {}", line))?;
-            let start = Cave {
-                name: start,
-                small: start.chars().all(|c| c.is_lowercase()),
-            };
-            let end = Cave {
-                name: end,
-                small: end.chars().all(|c| c.is_lowercase()),
-            };
+            let (start, end) = parse::split(input, line, "-")?;
+            let (start, end) = (parse_cave(input, start)?, parse_cave(input, end)?);
+            // There would be infinitely many paths going back and forth between them
+            if !start.small && !end.small {
+                return Err(ParseError::new(input, line, "Two large caves are connected").into());
+            }
             let start = *nodes
                 .entry(start.name)
                 .or_insert_with(|| graph.add_node(start));
@@ -90,6 +115,15 @@ fn can_visit_if_small(node: NodeIndex, path: &Path, graph: &UnGraph<Cave<'_>, ()
     }
 }
 
+/// The caves of a path, as `start,A,b,end`
+fn path_names(path: &Path, graph: &UnGraph<Cave<'_>, ()>) -> String {
+    path.nodes
+        .iter()
+        .map(|&node| graph.node_weight(node).unwrap().name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 fn cave_paths<F: Fn(NodeIndex, NodeIndex, &Path) -> bool>(
     nodes: &HashMap<&str, NodeIndex>,
     graph: &UnGraph<Cave<'_>, ()>,
@@ -110,9 +144,9 @@ fn cave_paths<F: Fn(NodeIndex, NodeIndex, &Path) -> bool>(
         let mut stuck = true;
 
         for path in paths {
-            // print_path(&path, &graph);
             let &last = path.nodes.last().unwrap();
             if last == end {
+                trace!(Debug, "path", caves = path_names(&path, graph));
                 new_paths.push(path);
             } else {
                 stuck = false;
@@ -138,14 +172,14 @@ fn cave_paths<F: Fn(NodeIndex, NodeIndex, &Path) -> bool>(
     }
 }
 
-pub(crate) fn part1((nodes, graph): Parsed) -> EyreResult<usize> {
+pub fn part1((nodes, graph): Parsed) -> EyreResult<usize> {
     Ok(cave_paths(&nodes, &graph, |neighbour, start, path| {
         can_visit(neighbour, start, path, &graph)
     })?
     .len())
 }
 
-pub(crate) fn part2((nodes, graph): Parsed) -> EyreResult<usize> {
+pub fn part2((nodes, graph): Parsed) -> EyreResult<usize> {
     Ok(cave_paths(&nodes, &graph, |neighbour, start, path| {
         can_visit_twice_once(neighbour, start, path, &graph)
     })?
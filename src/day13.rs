@@ -1,98 +1,127 @@
-use std::collections::HashSet;
+use std::{cmp::Ordering, collections::HashSet};
 
 use color_eyre::eyre;
 
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    picture::{self, Paint, Picture},
+    solution::Solution,
+    EyreResult,
+};
 
-day! {
-    parser,
-    part1 => "After one fold there are {} points",
-    part2 => "Pattern: \n{}",
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: usize = 13;
+    const TITLE: &'static str = "Transparent Origami";
+    const MESSAGES: [&'static str; 2] = ["After one fold there are {} points", "Pattern: \n{}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = String;
+
+    forward_solution!();
 }
 
 #[derive(Clone, Copy, Debug)]
-pub(crate) enum Axis {
+pub enum Axis {
     X,
     Y,
 }
 
-type Parsed = (HashSet<(u64, u64)>, Vec<(Axis, u64)>);
+pub type Parsed = (HashSet<(u64, u64)>, Vec<(Axis, u64)>);
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let (dots, folds) = split2(input, "\n\n").ok_or(eyre::eyre!("No empty line"))?;
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (dots, folds) = parse::split(input, input, "\n\n")?;
     let dots = dots
         .lines()
         .map(|line| -> EyreResult<_> {
-            let (x, y) = split2(line, ",").ok_or(eyre::eyre!("Invalid point: missing ','"))?;
-            Ok((x.parse()?, y.parse()?))
+            let (x, y) = parse::split(input, line, ",")?;
+            Ok((parse::token(input, x)?, parse::token(input, y)?))
         })
         .collect::<EyreResult<_>>()?;
 
-    let folds = folds
+    let folds: Vec<_> = folds
         .lines()
         .map(|line| -> EyreResult<_> {
             let p = line
                 .strip_prefix("fold along ")
-                .ok_or(eyre::eyre!("Lines does not start with 'fold along '"))?;
-            let (axis, dist) = split2(p, "=").ok_or(eyre::eyre!("No equal in line"))?;
+                .ok_or_else(|| ParseError::new(input, line, "Expected `fold along `"))?;
+            let (axis, dist) = parse::split(input, p, "=")?;
 
             let axis = match axis {
                 "x" => Axis::X,
                 "y" => Axis::Y,
-                _ => eyre::bail!("Invalid axis"),
+                _ => return Err(ParseError::new(input, axis, "Invalid axis").into()),
             };
 
-            Ok((axis, dist.parse()?))
+            Ok((axis, parse::token(input, dist)?))
         })
         .collect::<EyreResult<_>>()?;
 
+    if folds.is_empty() {
+        return Err(ParseError::new(input, "", "Expected fold instructions").into());
+    }
+
     Ok((dots, folds))
 }
 
-pub(crate) fn part1((mut points, fold): Parsed) -> EyreResult<usize> {
-    let (axis, idx) = fold[0];
-    match axis {
-        Axis::X => {
-            points = points
-                .drain()
-                .map(|(x, y)| if x > idx { (2 * idx - x, y) } else { (x, y) })
-                .collect();
-        }
-        Axis::Y => {
-            points = points
-                .drain()
-                .map(|(x, y)| if y > idx { (x, 2 * idx - y) } else { (x, y) })
-                .collect();
-        }
+/// Position of `coord` after folding along `line`, dots before the line do not move
+fn fold_coord(coord: u64, line: u64) -> EyreResult<u64> {
+    match coord.cmp(&line) {
+        Ordering::Less => Ok(coord),
+        Ordering::Equal => eyre::bail!("A dot is on the fold line {}", line),
+        Ordering::Greater => (2 * line)
+            .checked_sub(coord)
+            .ok_or_else(|| eyre::eyre!("Folding along {} moves {} past 0", line, coord)),
+    }
+}
+
+/// The dots of the sheet, read from right to left when `mirrored`
+fn sheet(points: &HashSet<(u64, u64)>, mirrored: bool) -> Picture {
+    let (max_x, max_y) = points.iter().fold((0, 0), |(max_x, max_y), &(x, y)| {
+        (max_x.max(x), max_y.max(y))
+    });
+    let mut picture = Picture::new(max_x as usize + 1, max_y as usize + 1);
+    for &(x, y) in points {
+        let x = if mirrored { max_x - x } else { x };
+        picture.set(x as usize, y as usize, Paint::Level(1.));
     }
+    picture
+}
+
+pub fn part1((points, fold): Parsed) -> EyreResult<usize> {
+    let (axis, idx) = fold[0];
+    let points = points
+        .into_iter()
+        .map(|(x, y)| match axis {
+            Axis::X => Ok((fold_coord(x, idx)?, y)),
+            Axis::Y => Ok((x, fold_coord(y, idx)?)),
+        })
+        .collect::<EyreResult<HashSet<_>>>()?;
+    picture::draw(|| sheet(&points, false));
     Ok(points.len())
 }
 
-pub(crate) fn part2((mut points, fold): Parsed) -> EyreResult<String> {
+pub fn part2((mut points, fold): Parsed) -> EyreResult<String> {
     for (axis, idx) in fold {
-        match axis {
-            Axis::X => {
-                points = points
-                    .drain()
-                    .map(|(x, y)| {
-                        if x < idx {
-                            (idx - x - 1, y)
-                        } else {
-                            (x - idx - 1, y)
-                        }
-                    })
-                    .collect();
-            }
-            Axis::Y => {
-                points = points
-                    .drain()
-                    .map(|(x, y)| if y > idx { (x, 2 * idx - y) } else { (x, y) })
-                    .collect();
-            }
-        }
+        points = points
+            .into_iter()
+            .map(|(x, y)| match axis {
+                Axis::X => match x.cmp(&idx) {
+                    Ordering::Less => Ok((idx - x - 1, y)),
+                    Ordering::Equal => eyre::bail!("A dot is on the fold line {}", idx),
+                    Ordering::Greater => Ok((x - idx - 1, y)),
+                },
+                Axis::Y => Ok((x, fold_coord(y, idx)?)),
+            })
+            .collect::<EyreResult<_>>()?;
     }
-    let &max_x = points.iter().map(|(x, _)| x).max().unwrap();
-    let &max_y = points.iter().map(|(_, y)| y).max().unwrap();
+    picture::draw(|| sheet(&points, true));
+    let (max_x, max_y) = points.iter().fold((0, 0), |(max_x, max_y), &(x, y)| {
+        (max_x.max(x), max_y.max(y))
+    });
 
     let mut s = String::new();
     for y in 0..=max_y {
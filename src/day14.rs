@@ -1,26 +1,55 @@
+use crate::{
+    forward_solution,
+    params::{AnyParam, Param},
+    parse::{self, ParseError},
+    solution::Solution,
+    EyreResult, Part,
+};
 use color_eyre::eyre;
+use std::{collections::HashMap, hash::Hash};
 
-use crate::{day, utils::split2, EyreResult};
-use std::collections::HashMap;
+pub struct Day14;
 
-day! {
-    parser,
-    part1 => "Most common minus least common is {}",
-    part2 => "Most common minus least common is {}",
+pub const STEPS: Param<usize> =
+    Param::per_part("steps", "Insertion steps applied to the template", 10, 40);
+
+impl Solution for Day14 {
+    const DAY: usize = 14;
+    const TITLE: &'static str = "Extended Polymerization";
+    const MESSAGES: [&'static str; 2] = [
+        "Most common minus least common is {}",
+        "Most common minus least common is {}",
+    ];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&STEPS];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-type Parsed = (Vec<u8>, HashMap<(u8, u8), u8>);
+pub type Parsed = (Vec<u8>, HashMap<(u8, u8), u8>);
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let (template, rules) = split2(input, "\n\n").ok_or(eyre::eyre!("No empty line"))?;
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (template, rules) = parse::split(input, input, "\n\n")?;
     let rules = rules
         .lines()
         .try_fold(HashMap::new(), |mut map, rule| -> EyreResult<_> {
-            let (pair, produce) = split2(rule, " -> ").ok_or(eyre::eyre!("rule has no ->"))?;
-            let pair = pair.as_bytes();
-            map.insert((pair[0], pair[1]), produce.as_bytes()[0]);
+            let (pair, produce) = parse::split(input, rule, " -> ")?;
+            match (pair.as_bytes(), produce.as_bytes()) {
+                (&[a, b], &[produce]) => map.insert((a, b), produce),
+                (&[_, _], _) => {
+                    return Err(ParseError::new(input, produce, "Expected one element").into())
+                }
+                _ => return Err(ParseError::new(input, pair, "Expected a pair").into()),
+            };
             Ok(map)
         })?;
+    let template = template.trim();
+    if template.is_empty() {
+        return Err(ParseError::new(input, template, "The template is empty").into());
+    }
     Ok((template.as_bytes().into(), rules))
 }
 
@@ -46,11 +75,20 @@ fn min_max_diff(amounts: &HashMap<u8, u64>) -> u64 {
     maxamount - minamount
 }
 
+/// Adds `amount` to the count of `key`, `None` when the count overflows
+fn add<K: Hash + Eq>(amounts: &mut HashMap<K, u64>, key: K, amount: u64) -> Option<()> {
+    let total = amounts.entry(key).or_insert(0);
+    *total = total.checked_add(amount)?;
+    Some(())
+}
+
 fn polymerize_pairs(
     chain: Vec<u8>,
     rules: HashMap<(u8, u8), u8>,
     steps: usize,
-) -> HashMap<u8, u64> {
+) -> EyreResult<HashMap<u8, u64>> {
+    let overflow = || eyre::eyre!("The number of elements overflows after {} steps", steps);
+
     let mut pairs = {
         let chain: &[u8] = &chain;
         chain
@@ -65,9 +103,14 @@ fn polymerize_pairs(
     for _ in 0..steps {
         let mut new_pairs = HashMap::new();
         for (pair, amount) in pairs {
-            let product = *rules.get(&pair).unwrap();
-            *new_pairs.entry((pair.0, product)).or_insert(0) += amount;
-            *new_pairs.entry((product, pair.1)).or_insert(0) += amount;
+            match rules.get(&pair) {
+                Some(&product) => {
+                    add(&mut new_pairs, (pair.0, product), amount).ok_or_else(overflow)?;
+                    add(&mut new_pairs, (product, pair.1), amount).ok_or_else(overflow)?;
+                }
+                // Nothing is inserted between pairs without a rule
+                None => add(&mut new_pairs, pair, amount).ok_or_else(overflow)?,
+            }
         }
         pairs = new_pairs;
     }
@@ -75,17 +118,45 @@ fn polymerize_pairs(
     let mut amounts = HashMap::new();
 
     for ((_, a), amount) in pairs {
-        *amounts.entry(a).or_insert(0) += amount;
+        add(&mut amounts, a, amount).ok_or_else(overflow)?;
     }
-    *amounts.entry(chain[0]).or_insert(0) += 1;
+    add(&mut amounts, chain[0], 1).ok_or_else(overflow)?;
+
+    Ok(amounts)
+}
 
-    amounts
+pub fn part1((template, rules): Parsed) -> EyreResult<u64> {
+    Ok(min_max_diff(&polymerize_pairs(
+        template,
+        rules,
+        STEPS.get(Part::Part1)?,
+    )?))
 }
 
-pub(crate) fn part1((template, rules): Parsed) -> EyreResult<u64> {
-    Ok(min_max_diff(&polymerize_pairs(template, rules, 10)))
+pub fn part2((template, rules): Parsed) -> EyreResult<u64> {
+    Ok(min_max_diff(&polymerize_pairs(
+        template,
+        rules,
+        STEPS.get(Part::Part2)?,
+    )?))
 }
 
-pub(crate) fn part2((template, rules): Parsed) -> EyreResult<u64> {
-    Ok(min_max_diff(&polymerize_pairs(template, rules, 40)))
+#[cfg(test)]
+mod test {
+    use super::{parser, polymerize_pairs};
+
+    #[test]
+    fn overflow() {
+        let input = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/day14"),
+        )
+        .unwrap();
+        let (template, rules) = parser(&input).unwrap();
+        assert!(polymerize_pairs(template.clone(), rules.clone(), 40).is_ok());
+        let err = polymerize_pairs(template, rules, 1000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The number of elements overflows after 1000 steps"
+        );
+    }
 }
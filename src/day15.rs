@@ -1,32 +1,44 @@
 use std::collections::{BinaryHeap, HashMap};
 
-use crate::{day, EyreResult};
+use crate::{
+    cancel, forward_solution,
+    params::{AnyParam, Param},
+    parse,
+    picture::{self, Paint, Picture},
+    solution::Solution,
+    visualize::{self, Cell, Frame},
+    EyreResult, Part,
+};
+use ansi_term::{Colour, Style};
+use color_eyre::eyre;
 use nalgebra::DMatrix;
 
-day! {
-    parser,
-    part1 => "Total risk is {}",
-    part2 => "Total risk is {}",
-}
+pub struct Day15;
+
+pub const TILES: Param<usize> = Param::new(
+    "tiles",
+    "Times the cave is repeated in each direction for part 2",
+    5,
+);
 
-type Parsed = DMatrix<u8>;
+impl Solution for Day15 {
+    const DAY: usize = 15;
+    const TITLE: &'static str = "Chiton";
+    const MESSAGES: [&'static str; 2] = ["Total risk is {}", "Total risk is {}"];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&TILES];
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let cols = input.lines().count();
-    let lines = input
-        .find('\n')
-        .ok_or(color_eyre::eyre::eyre!("No lines in input"))?;
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
+}
 
-    let table = DMatrix::from_iterator(
-        lines,
-        cols,
-        input
-            .lines()
-            .map(|b| b.trim().as_bytes().iter().map(|b| b - b'0'))
-            .flatten(),
-    );
+pub type Parsed = DMatrix<u8>;
 
-    Ok(table)
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (width, digits) = parse::digit_grid(input)?;
+    Ok(DMatrix::from_vec(width, digits.len() / width, digits))
 }
 
 fn neighbours<F>(
@@ -61,7 +73,7 @@ where
         .map(|p| (p, cost_eval(p)))
 }
 
-fn search_cost<F>(rows: usize, cols: usize, cost_eval: F) -> u64
+fn search_cost<F>(rows: usize, cols: usize, cost_eval: F) -> EyreResult<u64>
 where
     F: Fn((usize, usize)) -> u8,
 {
@@ -85,6 +97,10 @@ where
 
     let target = (rows - 1, cols - 1);
 
+    // Only needed to show the path
+    let trace = visualize::enabled() || picture::enabled();
+    let mut came_from = HashMap::new();
+
     let mut access = HashMap::new();
     let mut paths = BinaryHeap::new();
     paths.push(Path {
@@ -93,10 +109,15 @@ where
     });
 
     loop {
-        let path = paths.pop().unwrap();
+        cancel::check()?;
+        let path = paths
+            .pop()
+            .ok_or_else(|| eyre::eyre!("No path reaches the bottom right corner"))?;
 
         if path.node == target {
-            return path.cost;
+            visualize::show(|| path_frame(rows, cols, &cost_eval, &came_from, path.cost));
+            picture::draw(|| path_picture(rows, cols, &cost_eval, &came_from));
+            return Ok(path.cost);
         }
 
         if let Some(&acc) = access.get(&path.node) {
@@ -128,6 +149,9 @@ where
                 }
             };
             if keep {
+                if trace {
+                    came_from.insert(p, path.node);
+                }
                 paths.push(Path {
                     node: p,
                     cost: path_cost,
@@ -137,12 +161,83 @@ where
     }
 }
 
-pub(crate) fn part1(matrix: Parsed) -> EyreResult<u64> {
-    Ok(search_cost(matrix.nrows(), matrix.ncols(), |p| matrix[p]))
+/// The positions on the path to the bottom right corner
+fn path_to(
+    target: (usize, usize),
+    came_from: &HashMap<(usize, usize), (usize, usize)>,
+) -> Vec<(usize, usize)> {
+    let mut path = Vec::new();
+    let mut node = Some(target);
+    while let Some(p) = node {
+        path.push(p);
+        // The start can be reached again from its neighbours, its predecessor is not on the path
+        node = match p {
+            (0, 0) => None,
+            p => came_from.get(&p).copied(),
+        };
+    }
+    path
+}
+
+/// The risk of each position, highlighting the path to the bottom right corner
+fn path_frame<F>(
+    rows: usize,
+    cols: usize,
+    cost_eval: F,
+    came_from: &HashMap<(usize, usize), (usize, usize)>,
+    cost: u64,
+) -> Frame
+where
+    F: Fn((usize, usize)) -> u8,
+{
+    // The rows of the matrix are the columns of the cave
+    let mut frame = Frame::new(rows, cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            let glyph = char::from(b'0' + cost_eval((i, j)));
+            frame.set(i, j, Cell::styled(glyph, Style::new().dimmed()));
+        }
+    }
+    for p in path_to((rows - 1, cols - 1), came_from) {
+        let glyph = char::from(b'0' + cost_eval(p));
+        frame.set(p.0, p.1, Cell::styled(glyph, Colour::Red.bold()));
+    }
+    frame.caption(format!("Total risk is {}", cost))
+}
+
+/// The path over the risk of each position, as [`path_frame`]
+fn path_picture<F>(
+    rows: usize,
+    cols: usize,
+    cost_eval: F,
+    came_from: &HashMap<(usize, usize), (usize, usize)>,
+) -> Picture
+where
+    F: Fn((usize, usize)) -> u8,
+{
+    let mut picture = Picture::new(rows, cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            let level = cost_eval((i, j)).saturating_sub(1) as f32 / 8.;
+            picture.set(i, j, Paint::Level(level));
+        }
+    }
+    for (i, j) in path_to((rows - 1, cols - 1), came_from) {
+        picture.set(i, j, Paint::Highlight);
+    }
+    picture
 }
 
-pub(crate) fn part2(matrix: Parsed) -> EyreResult<u64> {
-    let mut total_matrix = DMatrix::zeros(matrix.nrows() * 5, matrix.ncols() * 5);
+pub fn part1(matrix: Parsed) -> EyreResult<u64> {
+    search_cost(matrix.nrows(), matrix.ncols(), |p| matrix[p])
+}
+
+pub fn part2(matrix: Parsed) -> EyreResult<u64> {
+    let tiles = TILES.get(Part::Part2)?;
+    if tiles == 0 {
+        eyre::bail!("The cave must be repeated at least once");
+    }
+    let mut total_matrix = DMatrix::zeros(matrix.nrows() * tiles, matrix.ncols() * tiles);
 
     for j in 0..matrix.ncols() {
         for i in 0..matrix.nrows() {
@@ -164,9 +259,7 @@ pub(crate) fn part2(matrix: Parsed) -> EyreResult<u64> {
         }
     }
 
-    Ok(search_cost(
-        total_matrix.nrows(),
-        total_matrix.ncols(),
-        |p| total_matrix[p],
-    ))
+    search_cost(total_matrix.nrows(), total_matrix.ncols(), |p| {
+        total_matrix[p]
+    })
 }
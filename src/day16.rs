@@ -1,20 +1,45 @@
-use crate::{day, EyreResult};
+use std::cell::Cell;
 
-day! {
-    parser,
-    part1 => "Sum of versions is {}",
-    part2 => "Value is {}",
+use color_eyre::eyre;
+
+use crate::{forward_solution, parse::ParseError, solution::Solution, EyreResult};
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: usize = 16;
+    const TITLE: &'static str = "Packet Decoder";
+    const MESSAGES: [&'static str; 2] = ["Sum of versions is {}", "Value is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-#[derive(Debug)]
-pub(crate) struct Packet {
+#[derive(Debug, Clone)]
+pub struct Packet {
     version: u8,
     ty: u8,
     payload: Payload,
 }
 
 impl Packet {
-    fn version_sum(&self) -> u64 {
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn type_id(&self) -> u8 {
+        self.ty
+    }
+
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Sum of the versions of this packet and all its sub-packets
+    pub fn version_sum(&self) -> u64 {
         match &self.payload {
             Payload::Litteral(_) => self.version as u64,
             Payload::Operator(v) => {
@@ -23,40 +48,49 @@ impl Packet {
         }
     }
 
-    fn calculate(&self) -> u64 {
+    /// Value of the expression represented by the packet
+    pub fn calculate(&self) -> EyreResult<u64> {
         match &self.payload {
-            Payload::Litteral(v) => *v,
+            Payload::Litteral(v) => Ok(*v),
             Payload::Operator(v) => {
-                let mut values = v.iter().map(Self::calculate);
-                match self.ty {
-                    0 => values.sum(),
-                    1 => values.product(),
-                    2 => values.min().unwrap(),
-                    3 => values.max().unwrap(),
-                    5 | 6 | 7 => {
-                        let a = values.next().unwrap();
-                        let b = values.next().unwrap();
-                        (match self.ty {
-                            5 => a > b,
-                            6 => a < b,
-                            7 => a == b,
-                            _ => unreachable!(),
-                        }) as u64
-                    }
+                let values = v
+                    .iter()
+                    .map(Self::calculate)
+                    .collect::<EyreResult<Vec<_>>>()?;
+                let empty = || eyre::eyre!("Operator packet has no sub-packets");
+                Ok(match self.ty {
+                    0 => values.iter().sum(),
+                    1 => values.iter().product(),
+                    2 => *values.iter().min().ok_or_else(empty)?,
+                    3 => *values.iter().max().ok_or_else(empty)?,
+                    5..=7 => match values[..] {
+                        [a, b] => {
+                            (match self.ty {
+                                5 => a > b,
+                                6 => a < b,
+                                7 => a == b,
+                                _ => unreachable!(),
+                            }) as u64
+                        }
+                        _ => eyre::bail!(
+                            "Comparison packet has {} sub-packets instead of 2",
+                            values.len()
+                        ),
+                    },
                     _ => unreachable!(),
-                }
+                })
             }
         }
     }
 }
 
-#[derive(Debug)]
-pub(crate) enum Payload {
+#[derive(Debug, Clone)]
+pub enum Payload {
     Litteral(u64),
     Operator(Vec<Packet>),
 }
 
-type Parsed = Packet;
+pub type Parsed = Packet;
 
 fn bits(v: u8) -> impl Iterator<Item = Bit> {
     (0..8).rev().map(move |i| ((1 << i) & v) >> i)
@@ -85,124 +119,181 @@ fn bit_stream(input: &str) -> impl Iterator<Item = Bit> + '_ {
         .flatten()
 }
 
-fn num<I>(bit_count: usize, bits: &mut I) -> u64
+/// Why a transmission could not be decoded
+enum DecodeError {
+    /// The transmission ends in the middle of a packet
+    Truncated,
+    Malformed(&'static str),
+}
+
+fn num<I>(bit_count: usize, bits: &mut I) -> Result<u64, DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
-    bits.take(bit_count)
-        .fold(0, |current, digit| current << 1 | digit as u64)
+    (0..bit_count).try_fold(0, |current, _| {
+        Ok(current << 1 | bits.next().ok_or(DecodeError::Truncated)? as u64)
+    })
 }
 
-fn take_bool<I>(bits: &mut I) -> bool
+fn take_bool<I>(bits: &mut I) -> Result<bool, DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
-    bits.next().unwrap() == 1
+    Ok(bits.next().ok_or(DecodeError::Truncated)? == 1)
 }
 
-fn parse_group<I>(bits: &mut I) -> ((bool, u8), usize)
+fn parse_group<I>(bits: &mut I) -> Result<((bool, u8), usize), DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
-    let last = !take_bool(bits);
-    ((last, num(4, bits) as u8), 1 + 4)
+    let last = !take_bool(bits)?;
+    Ok(((last, num(4, bits)? as u8), 1 + 4))
 }
 
-fn parse_literal<I>(bits: &mut I) -> (u64, usize)
+fn parse_literal<I>(bits: &mut I) -> Result<(u64, usize), DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
     let mut val = 0;
     let mut read = 0;
     loop {
-        let ((last, v), r) = parse_group(bits);
+        let ((last, v), r) = parse_group(bits)?;
         read += r;
         val = val << 4 | v as u64;
         if last {
-            break (val, read);
+            break Ok((val, read));
         }
     }
 }
 
-fn parse_operator<I>(bits: &mut I) -> (Vec<Packet>, usize)
+fn parse_operator<I>(bits: &mut I) -> Result<(Vec<Packet>, usize), DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
-    let is_packet_count = take_bool(bits);
+    let is_packet_count = take_bool(bits)?;
 
     let mut read;
     let count = if is_packet_count {
         read = 1 + 11;
-        num(11, bits)
+        num(11, bits)?
     } else {
         read = 1 + 15;
-        num(15, bits)
+        num(15, bits)?
     };
 
     if is_packet_count {
         let mut sub_packets = Vec::with_capacity(count as usize);
 
         for _ in 0..count {
-            let (packet, r) = parse_packet(bits);
+            let (packet, r) = parse_packet(bits)?;
             read += r;
             sub_packets.push(packet);
         }
 
-        (sub_packets, read)
+        Ok((sub_packets, read))
     } else {
         let mut remain = count;
 
         let mut sub_packets = Vec::new();
         while remain != 0 {
-            let (packet, r) = parse_packet(bits);
-            remain -= r as u64;
+            let (packet, r) = parse_packet(bits)?;
+            remain = remain.checked_sub(r as u64).ok_or(DecodeError::Malformed(
+                "Sub-packets are longer than the length of their operator",
+            ))?;
             read += r;
             sub_packets.push(packet);
         }
-        (sub_packets, read)
+        Ok((sub_packets, read))
     }
 }
 
-fn parse_packet<I>(bits: &mut I) -> (Packet, usize)
+fn parse_packet<I>(bits: &mut I) -> Result<(Packet, usize), DecodeError>
 where
     I: Iterator<Item = Bit>,
 {
-    let version = num(3, bits) as u8;
-    let id = num(3, bits);
+    let version = num(3, bits)? as u8;
+    let id = num(3, bits)?;
     if id == 4 {
-        let (payload, read) = parse_literal(bits);
-        (
+        let (payload, read) = parse_literal(bits)?;
+        Ok((
             Packet {
                 version,
                 ty: id as u8,
                 payload: Payload::Litteral(payload),
             },
             read + 6,
-        )
+        ))
     } else {
-        let (payload, read) = parse_operator(bits);
-        (
+        let (payload, read) = parse_operator(bits)?;
+        match (id, payload.len()) {
+            (2 | 3, 0) => {
+                return Err(DecodeError::Malformed(
+                    "Minimum and maximum packets need sub-packets",
+                ))
+            }
+            (5..=7, len) if len != 2 => {
+                return Err(DecodeError::Malformed(
+                    "Comparison packets need two sub-packets",
+                ))
+            }
+            _ => (),
+        }
+        Ok((
             Packet {
                 version,
                 ty: id as u8,
                 payload: Payload::Operator(payload),
             },
             read + 6,
-        )
+        ))
     }
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let mut stream = bit_stream(input);
-    Ok(parse_packet(&mut stream).0)
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let hex = input.trim();
+    if let Some((i, c)) = hex
+        .char_indices()
+        .find(|&(_, c)| !matches!(c, '0'..='9' | 'A'..='F'))
+    {
+        return Err(ParseError::new(
+            input,
+            &hex[i..i + c.len_utf8()],
+            "Expected an hexadecimal digit",
+        )
+        .into());
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(ParseError::new(
+            input,
+            &hex[hex.len() - 1..],
+            "The transmission has an odd number of digits",
+        )
+        .into());
+    }
+
+    let read = Cell::new(0usize);
+    let mut stream = bit_stream(hex).inspect(|_| read.set(read.get() + 1));
+    match parse_packet(&mut stream) {
+        Ok((packet, _)) => Ok(packet),
+        Err(e) => {
+            // Points at the digit holding the last bit that was read
+            let digit = read.get().saturating_sub(1) / 4;
+            let at = hex.get(digit..digit + 1).unwrap_or(&hex[hex.len()..]);
+            let message = match e {
+                DecodeError::Truncated => "The transmission ends in the middle of a packet",
+                DecodeError::Malformed(message) => message,
+            };
+            Err(ParseError::new(input, at, message).into())
+        }
+    }
 }
 
-pub(crate) fn part1(packet: Parsed) -> EyreResult<u64> {
+pub fn part1(packet: Parsed) -> EyreResult<u64> {
     Ok(packet.version_sum())
 }
 
-pub(crate) fn part2(packet: Parsed) -> EyreResult<u64> {
-    Ok(packet.calculate())
+pub fn part2(packet: Parsed) -> EyreResult<u64> {
+    packet.calculate()
 }
 
 #[cfg(test)]
@@ -224,6 +315,33 @@ mod test {
         )
     }
 
+    #[test]
+    fn malformed() {
+        use super::parser;
+
+        let err = |input| parser(input).unwrap_err().to_string();
+        assert!(err("D2FG28").contains("(line 1, column 4)"));
+        // Literal packet cut in its second group
+        assert!(err("D2FE").contains("ends in the middle of a packet (line 1, column 4)"));
+        // Minimum operator with no sub-packets
+        assert!(err("4A0000").contains("need sub-packets"));
+    }
+
+    #[test]
+    fn empty_operator() {
+        use super::{Packet, Payload};
+
+        let packet = |ty, payload| Packet {
+            version: 0,
+            ty,
+            payload: Payload::Operator(payload),
+        };
+        let err = packet(2, vec![]).calculate().unwrap_err().to_string();
+        assert_eq!(err, "Operator packet has no sub-packets");
+        assert!(packet(6, vec![packet(0, vec![])]).calculate().is_err());
+        assert_eq!(packet(1, vec![packet(0, vec![])]).calculate().unwrap(), 0);
+    }
+
     #[test]
     fn bit_stream() {
         use super::bit_stream;
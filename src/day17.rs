@@ -1,36 +1,59 @@
 use std::ops::RangeInclusive;
 
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    trace, EyreResult,
+};
 
-day! {
-    parser,
-    part1 => "May y value: {}",
-    part2 => "Number of probe launch configurations: {}"
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: usize = 17;
+    const TITLE: &'static str = "Trick Shot";
+    const MESSAGES: [&'static str; 2] = [
+        "May y value: {}",
+        "Number of probe launch configurations: {}",
+    ];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = i64;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-#[derive(Debug)]
-pub(crate) struct Target {
+#[derive(Debug, Clone)]
+pub struct Target {
     x: RangeInclusive<i64>,
     y: RangeInclusive<i64>,
 }
 
-type Parsed = Target;
+pub type Parsed = Target;
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let input = input.trim().trim_start_matches("target area: ");
-    let (x, y) = split2(input, ", ").ok_or(color_eyre::eyre::eyre!("Malformed input"))?;
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let area = input.trim().trim_start_matches("target area: ");
+    let (x, y) = parse::split(input, area, ", ")?;
 
-    fn parse_range(range: &str) -> EyreResult<RangeInclusive<i64>> {
-        let (_, range) =
-            split2(range, "=").ok_or(color_eyre::eyre::eyre!("Malformed range definition"))?;
-        let (min, max) = split2(range, "..").ok_or(color_eyre::eyre::eyre!("Malformed range"))?;
-        Ok(min.parse()?..=max.parse()?)
-    }
+    let parse_range = |range: &str| -> Result<RangeInclusive<i64>, ParseError> {
+        let (_, bounds) = parse::split(input, range, "=")?;
+        let (min, max) = parse::split(input, bounds, "..")?;
+        Ok(parse::token(input, min)?..=parse::token(input, max)?)
+    };
 
-    Ok(Target {
+    let target = Target {
         x: parse_range(x)?,
         y: parse_range(y)?,
-    })
+    };
+    // The solution relies on the probe being shot forward, above the target
+    if *target.x.start() <= 0 {
+        return Err(ParseError::new(input, x, "The target must be ahead of the probe").into());
+    }
+    if *target.y.end() >= 0 {
+        return Err(ParseError::new(input, y, "The target must be below the probe").into());
+    }
+    Ok(target)
 }
 
 fn yn(vy0: i64, n: i64) -> i64 {
@@ -77,7 +100,7 @@ fn int_in_range((step_in, step_out): (f64, f64)) -> bool {
     if step_out.is_nan() {
         !step_in.is_nan()
     } else {
-        //dbg!(step_in, step_out);
+        trace!(Debug, "steps", entry = step_in, exit = step_out);
         step_out.floor() >= step_in.ceil()
     }
 }
@@ -124,19 +147,19 @@ mod test {
     }
 }
 
-// 
+//
 // The main idea is that y(n) = n*vy0 - n(n-1)/2
 // x(n) is the same uptil x(vx0), and constant after
 // ymax/xmax = C(vC0)
 //
 
-pub(crate) fn part1(target: Parsed) -> EyreResult<i64> {
-    Ok((0..1000)
+pub fn part1(target: Parsed) -> EyreResult<i64> {
+    (0..1000)
         .filter(|&vy0| steps_y_into(vy0 as f64, *target.y.end() as f64, *target.y.start() as f64))
         // for n = vy0 we have the maximum y value
         .map(|vy0| yn(vy0, vy0))
         .max()
-        .unwrap())
+        .ok_or_else(|| color_eyre::eyre::eyre!("No velocity reaches the target"))
 }
 
 fn overlap_range(r1: (f64, f64), r2: (f64, f64)) -> Option<(f64, f64)> {
@@ -167,7 +190,7 @@ fn overlap_range(r1: (f64, f64), r2: (f64, f64)) -> Option<(f64, f64)> {
     }
 }
 
-pub(crate) fn part2(target: Parsed) -> EyreResult<usize> {
+pub fn part2(target: Parsed) -> EyreResult<usize> {
     let min_vx = vx0_for_xmax(*target.x.start()).ceil() as i64;
     let max_vx = *target.x.end();
     let count = (min_vx..=max_vx)
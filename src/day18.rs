@@ -1,13 +1,26 @@
-use crate::{day, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    trace, EyreResult,
+};
 
-day! {
-    parser,
-    part1 => "Sum of all numbers is {}",
-    part2 => "Largest sum is {}",
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: usize = 18;
+    const TITLE: &'static str = "Snailfish";
+    const MESSAGES: [&'static str; 2] = ["Sum of all numbers is {}", "Largest sum is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
 #[derive(Clone)]
-pub(crate) enum Pair {
+pub enum Pair {
     Number(u64),
     Composite(Box<Pair>, Box<Pair>),
 }
@@ -86,15 +99,18 @@ impl Pair {
         }
     }
 
-    fn reduce(&mut self) {
+    /// Applies explosions and splits until the number is reduced
+    pub fn reduce(&mut self) {
         loop {
             let (_, _, reduced_explode) = self.exploding_reduce(0);
             if reduced_explode {
+                trace!(Debug, "explode", number = self);
                 continue;
             }
 
             let reduced_split = self.split();
             if reduced_split {
+                trace!(Debug, "split", number = self);
                 continue;
             }
 
@@ -102,7 +118,7 @@ impl Pair {
         }
     }
 
-    fn magnitude(&self) -> u64 {
+    pub fn magnitude(&self) -> u64 {
         match self {
             &Pair::Number(n) => n,
             Pair::Composite(l, r) => l.magnitude() * 3 + r.magnitude() * 2,
@@ -110,6 +126,17 @@ impl Pair {
     }
 }
 
+/// Snailfish addition, the result is reduced
+impl std::ops::Add for Pair {
+    type Output = Pair;
+
+    fn add(self, rhs: Pair) -> Pair {
+        let mut sum = Pair::Composite(Box::new(self), Box::new(rhs));
+        sum.reduce();
+        sum
+    }
+}
+
 impl std::fmt::Display for Pair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -119,24 +146,43 @@ impl std::fmt::Display for Pair {
     }
 }
 
-type Parsed = Vec<Pair>;
-
-fn parse_pair(pair: &str) -> Pair {
-    let mut stack = Vec::new();
-    for b in pair.bytes() {
-        match b {
-            b'[' | b',' => (),
-            b'0'..=b'9' => stack.push(Pair::Number((b - b'0') as u64)),
-            b']' => {
-                let r = Box::new(stack.pop().unwrap());
-                let l = Box::new(stack.pop().unwrap());
-                stack.push(Pair::Composite(l, r));
-            }
-            _ => unreachable!(),
+pub type Parsed = Vec<Pair>;
+
+/// Parses the number at the start of `text`, a slice of `input`, giving it with the text after it
+fn parse_element<'t>(input: &str, text: &'t str) -> Result<(Pair, &'t str), ParseError> {
+    let next = |text: &'t str| &text[..text.chars().next().map_or(0, char::len_utf8)];
+    let expect = |text: &'t str, c: char| {
+        text.strip_prefix(c)
+            .ok_or_else(|| ParseError::new(input, next(text), format!("Expected {:?}", c)))
+    };
+
+    if let Some(rest) = text.strip_prefix('[') {
+        let (l, rest) = parse_element(input, rest)?;
+        let rest = expect(rest, ',')?;
+        let (r, rest) = parse_element(input, rest)?;
+        let rest = expect(rest, ']')?;
+        Ok((Pair::Composite(Box::new(l), Box::new(r)), rest))
+    } else {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        if end == 0 {
+            return Err(ParseError::new(input, next(text), "Expected a number"));
         }
+        let number = parse::token(input, &text[..end])?;
+        Ok((Pair::Number(number), &text[end..]))
+    }
+}
+
+fn parse_pair(input: &str, pair: &str) -> Result<Pair, ParseError> {
+    match parse_element(input, pair)? {
+        (pair, "") => Ok(pair),
+        (_, rest) => Err(ParseError::new(
+            input,
+            rest,
+            "Unexpected text after the number",
+        )),
     }
-    assert!(stack.len() == 1);
-    stack.pop().unwrap()
 }
 
 #[cfg(test)]
@@ -163,7 +209,11 @@ mod test {
 
     #[test]
     fn complete_reduce() {
-        let mut pair = parse_pair("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+        let mut pair = parse_pair(
+            "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]",
+            "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]",
+        )
+        .unwrap();
         pair.reduce();
         assert_eq!(pair.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
     }
@@ -172,7 +222,7 @@ mod test {
     fn explode() {
         macro_rules! test_explode {
             ($in:expr, $out:expr, $l:expr, $r:expr) => {
-                let mut pair = parse_pair($in);
+                let mut pair = parse_pair($in, $in).unwrap();
                 let (left, right, reduced) = pair.exploding_reduce(0);
                 assert!(reduced);
                 assert_eq!(pair.to_string(), $out);
@@ -190,7 +240,7 @@ mod test {
     fn parsing() {
         macro_rules! parse_test {
             ($e:expr) => {
-                assert_eq!($e, parse_pair($e).to_string())
+                assert_eq!($e, parse_pair($e, $e).unwrap().to_string())
             };
         }
 
@@ -200,38 +250,52 @@ mod test {
         parse_test!("[[1,9],[8,5]]");
         parse_test!("[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]");
     }
+
+    #[test]
+    fn malformed() {
+        let err = |input| super::parser(input).err().unwrap().to_string();
+        assert!(err("[1,2]\n[[1,2]3]").ends_with("2 | [[1,2]3]\n  |       ^"));
+        assert!(err("[1,2]]").contains("Unexpected text after the number (line 1, column 6)"));
+        assert!(err("[1,").contains("Expected a number (line 1, column 4)"));
+    }
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    Ok(input.lines().map(|l| parse_pair(l.trim())).collect())
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let numbers = input
+        .lines()
+        .map(|l| parse_pair(input, l.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.is_empty() {
+        return Err(ParseError::new(input, input, "There are no numbers to add").into());
+    }
+    Ok(numbers)
 }
 
 fn list_add(p: Vec<Pair>) -> Pair {
     let mut numbers = p.into_iter();
     let start = numbers.next().unwrap();
     numbers.fold(start, |current, pair| {
-        let mut next = Pair::Composite(Box::new(current), Box::new(pair));
-        next.reduce();
-        next
+        let sum = current + pair;
+        trace!(Info, "sum", number = sum);
+        sum
     })
 }
 
-pub(crate) fn part1(numbers: Parsed) -> EyreResult<u64> {
+pub fn part1(numbers: Parsed) -> EyreResult<u64> {
     let result = list_add(numbers);
     Ok(result.magnitude())
 }
 
-pub(crate) fn part2(numbers: Parsed) -> EyreResult<u64> {
+pub fn part2(numbers: Parsed) -> EyreResult<u64> {
     let mut max = 0;
     for (ix, x) in numbers.iter().enumerate() {
         for (iy, y) in numbers.iter().enumerate() {
             if ix == iy {
                 continue;
             }
-            let mut res = Pair::Composite(Box::new(x.clone()), Box::new(y.clone()));
-            res.reduce();
-            let mag = res.magnitude();
+            let mag = (x.clone() + y.clone()).magnitude();
             if mag > max {
+                trace!(Info, "largest", left = ix, right = iy, magnitude = mag);
                 max = mag;
             }
         }
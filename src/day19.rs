@@ -1,23 +1,47 @@
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    cancel::{self, Cancelled},
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    trace, EyreResult,
+};
+use color_eyre::eyre;
 use std::{
     collections::{HashMap, HashSet},
     ops::{Add, Sub},
 };
 
-day! {
-    parser,
-    part1 => "There are {} beacons",
-    part2 => "The largest distance is {}",
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: usize = 19;
+    const TITLE: &'static str = "Beacon Scanner";
+    const MESSAGES: [&'static str; 2] = ["There are {} beacons", "The largest distance is {}"];
+    const SHARED_STEP: bool = true;
+
+    type Parsed<'i> = Aligned;
+    type Answer1 = usize;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> EyreResult<Self::Parsed<'_>> {
+        Ok((parser(input)?, None))
+    }
+
+    fn shared((scanners, _): Self::Parsed<'_>) -> EyreResult<Self::Parsed<'_>> {
+        align(scanners)
+    }
+
+    forward_solution!(part1, part2);
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) struct Vec3 {
-    x: i64,
-    y: i64,
-    z: i64,
+pub struct Vec3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
 }
 
-type Rotation = [[i64; 3]; 3];
+pub type Rotation = [[i64; 3]; 3];
 
 const ROTATION_GROUP: [Rotation; 24] = [
     [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
@@ -73,16 +97,26 @@ fn try_map<'i>(
     reference: &'i [Vec3],
     other: &'i [Vec3],
 ) -> impl Iterator<Item = (Vec3, Rotation)> + 'i {
-    ROTATION_GROUP.into_iter().filter_map(|rotation| {
-        let mapping = possible_dists(reference, other, rotation);
-        let mut mapping = mapping.iter().filter(|(_, v)| **v >= 12);
-        if let Some((off, _)) = mapping.next() {
-            assert!(mapping.next().is_none());
-            Some((*off, rotation))
-        } else {
-            None
-        }
-    })
+    ROTATION_GROUP
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, rotation)| {
+            let mapping = possible_dists(reference, other, rotation);
+            let mut mapping = mapping.iter().filter(|(_, v)| **v >= 12);
+            if let Some((off, &beacons)) = mapping.next() {
+                assert!(mapping.next().is_none());
+                trace!(
+                    Debug,
+                    "overlap",
+                    rotation = idx,
+                    offset = off,
+                    beacons = beacons
+                );
+                Some((*off, rotation))
+            } else {
+                None
+            }
+        })
 }
 
 fn recover_position(
@@ -90,9 +124,9 @@ fn recover_position(
     scanners: &[Vec<Vec3>],
     current: &mut HashMap<(usize, usize), (Vec3, Rotation)>,
     evaluating: &mut HashSet<(usize, usize)>,
-) -> Option<(Vec3, Rotation)> {
+) -> Result<Option<(Vec3, Rotation)>, Cancelled> {
     if idx == 0 {
-        Some((Vec3 { x: 0, y: 0, z: 0 }, ROTATION_GROUP[0]))
+        Ok(Some((Vec3 { x: 0, y: 0, z: 0 }, ROTATION_GROUP[0])))
     } else {
         'other: for (refidx, reference) in scanners.iter().enumerate() {
             if refidx == idx {
@@ -100,9 +134,10 @@ fn recover_position(
             }
 
             match current.get(&(idx, refidx)) {
-                Some(p) => return Some(*p),
+                Some(p) => return Ok(Some(*p)),
                 None => (),
             }
+            cancel::check()?;
 
             for (mappos, maprot) in try_map(reference, &scanners[idx]) {
                 let key = (idx, refidx);
@@ -112,7 +147,7 @@ fn recover_position(
                     evaluating.insert(revkey);
 
                     let (refpos, refrot) =
-                        match recover_position(refidx, scanners, current, evaluating) {
+                        match recover_position(refidx, scanners, current, evaluating)? {
                             None => {
                                 evaluating.remove(&key);
                                 evaluating.remove(&revkey);
@@ -128,18 +163,25 @@ fn recover_position(
                     let position = rotatated_pos + refpos;
                     let total_rot = compose_rotation(refrot, maprot);
 
+                    trace!(
+                        Info,
+                        "placed",
+                        scanner = idx,
+                        reference = refidx,
+                        position = position
+                    );
                     current.insert((idx, refidx), (position, total_rot));
 
-                    return Some((position, total_rot));
+                    return Ok(Some((position, total_rot)));
                 }
             }
         }
 
-        None
+        Ok(None)
     }
 }
 
-fn recover_positions(scanners: &[Vec<Vec3>]) -> Vec<(Vec3, Rotation)> {
+fn recover_positions(scanners: &[Vec<Vec3>]) -> EyreResult<Vec<(Vec3, Rotation)>> {
     let mut coords = Vec::with_capacity(scanners.len());
     coords.push((Vec3 { x: 0, y: 0, z: 0 }, ROTATION_GROUP[0]));
     let mut mappings = HashMap::new();
@@ -147,13 +189,14 @@ fn recover_positions(scanners: &[Vec<Vec3>]) -> Vec<(Vec3, Rotation)> {
 
     for idx in 1..scanners.len() {
         coords.push(
-            recover_position(idx, scanners, &mut mappings, &mut evaluating)
-                .expect("Should always find 0"),
+            recover_position(idx, scanners, &mut mappings, &mut evaluating)?.ok_or_else(|| {
+                eyre::eyre!("Scanner {} can not be placed relative to scanner 0", idx)
+            })?,
         );
         assert!(evaluating.is_empty());
     }
 
-    coords
+    Ok(coords)
 }
 
 impl Vec3 {
@@ -161,7 +204,7 @@ impl Vec3 {
         self.x * line[0] + self.y * line[1] + self.z * line[2]
     }
 
-    fn matmul(&self, mat: [[i64; 3]; 3]) -> Self {
+    pub fn matmul(&self, mat: [[i64; 3]; 3]) -> Self {
         Self {
             x: self.dot(mat[0]),
             y: self.dot(mat[1]),
@@ -169,11 +212,18 @@ impl Vec3 {
         }
     }
 
-    fn norm(&self) -> i64 {
+    /// Manhattan norm
+    pub fn norm(&self) -> i64 {
         self.x.abs() + self.y.abs() + self.z.abs()
     }
 }
 
+impl std::fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
 impl Sub for Vec3 {
     type Output = Vec3;
 
@@ -257,6 +307,7 @@ mod test {
 
         assert_eq!(
             recover_positions(&scanners)
+                .unwrap()
                 .into_iter()
                 .map(|(pos, _)| pos)
                 .collect::<Vec<_>>(),
@@ -269,26 +320,43 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn unaligned() {
+        use super::{align, part1, part2};
+
+        let input = include_str!("../examples/day19");
+        let scanners = parser(input).unwrap();
+
+        let err = part1((scanners.clone(), None)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The scanners must be aligned before solving"
+        );
+        assert!(part2((scanners.clone(), None)).is_err());
+        assert_eq!(part1(align(scanners).unwrap()).unwrap(), 79);
+    }
 }
 
-type Parsed = Vec<Vec<Vec3>>;
+pub type Parsed = Vec<Vec<Vec3>>;
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input
         .split("\n\n")
         .map(|scanner| {
-            scanner
-                .lines()
-                .skip(1)
+            let mut lines = scanner.lines();
+            let header = lines.next().unwrap_or_default();
+            if !header.starts_with("--- scanner") {
+                return Err(ParseError::new(input, header, "Expected a scanner header").into());
+            }
+            lines
                 .map(|coords| {
-                    let (x, yz) =
-                        split2(coords, ",").ok_or(color_eyre::eyre::eyre!("malformed coords"))?;
-                    let (y, z) =
-                        split2(yz, ",").ok_or(color_eyre::eyre::eyre!("malformed coords"))?;
+                    let (x, yz) = parse::split(input, coords, ",")?;
+                    let (y, z) = parse::split(input, yz, ",")?;
                     Ok(Vec3 {
-                        x: x.parse()?,
-                        y: y.parse()?,
-                        z: z.trim().parse()?,
+                        x: parse::token(input, x)?,
+                        y: parse::token(input, y)?,
+                        z: parse::token(input, z.trim())?,
                     })
                 })
                 .collect()
@@ -296,9 +364,21 @@ pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
         .collect()
 }
 
-pub(crate) fn part1(scanners: Parsed) -> EyreResult<usize> {
-    let positions = recover_positions(&scanners);
+/// Scanners along with their position and rotation relative to the first one, `None` until
+/// they are aligned
+pub type Aligned = (Parsed, Option<Vec<(Vec3, Rotation)>>);
+
+pub fn align(scanners: Parsed) -> EyreResult<Aligned> {
+    let positions = recover_positions(&scanners)?;
+    Ok((scanners, Some(positions)))
+}
 
+fn aligned(positions: Option<Vec<(Vec3, Rotation)>>) -> EyreResult<Vec<(Vec3, Rotation)>> {
+    positions.ok_or_else(|| eyre::eyre!("The scanners must be aligned before solving"))
+}
+
+pub fn part1((scanners, positions): Aligned) -> EyreResult<usize> {
+    let positions = aligned(positions)?;
     let mut beacons = HashSet::new();
     for (scanner, (refpos, refrot)) in scanners.iter().zip(&positions) {
         for beacon in scanner {
@@ -310,14 +390,13 @@ pub(crate) fn part1(scanners: Parsed) -> EyreResult<usize> {
     Ok(beacons.len())
 }
 
-pub(crate) fn part2(scanners: Parsed) -> EyreResult<i64> {
-    let positions = recover_positions(&scanners);
-
+pub fn part2((_, positions): Aligned) -> EyreResult<i64> {
+    let positions = aligned(positions)?;
     Ok(positions
         .iter()
         .map(|&(x, _)| positions.iter().map(move |&(y, _)| (x, y)))
         .flatten()
         .map(|(x, y)| (x - y).norm())
         .max()
-        .unwrap())
+        .unwrap_or(0))
 }
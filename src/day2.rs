@@ -1,14 +1,23 @@
-use crate::{day, EyreResult};
+use crate::{forward_solution, parse, solution::Solution, EyreResult};
 use color_eyre::eyre;
 use std::str::FromStr;
 
-day! {
-    parser,
-    part1 => "Result = {}",
-    part2 => "Result = {}",
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: usize = 2;
+    const TITLE: &'static str = "Dive!";
+    const MESSAGES: [&'static str; 2] = ["Result = {}", "Result = {}"];
+
+    type Parsed<'i> = Vec<(Action, u32)>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    forward_solution!();
 }
 
-pub(crate) enum Action {
+#[derive(Clone, Copy)]
+pub enum Action {
     Forward,
     Up,
     Down,
@@ -27,25 +36,25 @@ impl FromStr for Action {
     }
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Vec<(Action, u32)>> {
+pub fn parser(input: &str) -> EyreResult<Vec<(Action, u32)>> {
     input
         .lines()
         .map(|l| {
-            let mut parts = l.split(" ");
+            let (action, distance) = parse::split(input, l, " ")?;
             Ok((
-                parts.next().ok_or(eyre::eyre!("No action"))?.parse()?,
-                parts.next().ok_or(eyre::eyre!("No distance"))?.parse()?,
+                parse::token(input, action)?,
+                parse::token(input, distance.trim())?,
             ))
         })
         .collect()
 }
 
-pub(crate) struct State {
+pub struct State {
     horizontal: u64,
     vertical: i64,
 }
 
-pub(crate) fn part1(instructions: Vec<(Action, u32)>) -> EyreResult<i64> {
+pub fn part1(instructions: Vec<(Action, u32)>) -> EyreResult<i64> {
     let state = instructions.iter().fold(
         State {
             horizontal: 0,
@@ -64,13 +73,13 @@ pub(crate) fn part1(instructions: Vec<(Action, u32)>) -> EyreResult<i64> {
     Ok(state.horizontal as i64 * state.vertical)
 }
 
-pub(crate) struct AimState {
+pub struct AimState {
     horizontal: u64,
     vertical: i64,
     aim: i64,
 }
 
-pub(crate) fn part2(instructions: Vec<(Action, u32)>) -> EyreResult<i64> {
+pub fn part2(instructions: Vec<(Action, u32)>) -> EyreResult<i64> {
     let aim_state = instructions.iter().fold(
         AimState {
             horizontal: 0,
@@ -1,39 +1,81 @@
-use arrayvec::ArrayVec;
+use color_eyre::eyre;
 
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    cancel, forward_solution,
+    params::{AnyParam, Param},
+    parse::{self, ParseError},
+    picture::{self, Paint, Picture},
+    solution::Solution,
+    visualize::{self, Cell, Frame},
+    EyreResult, Part,
+};
+use ansi_term::{Colour, Style};
 use std::{cmp, collections::HashSet};
 
-day! {
-    parser,
-    part1 => "There are {} pixels",
-    part2 => "There are {} pixels after 50 rounds",
+pub struct Day20;
+
+pub const ROUNDS: Param<usize> =
+    Param::per_part("rounds", "Times the enhancement is applied", 2, 50);
+
+impl Solution for Day20 {
+    const DAY: usize = 20;
+    const TITLE: &'static str = "Trench Map";
+    const MESSAGES: [&'static str; 2] = [
+        "There are {} pixels",
+        "There are {} pixels after {rounds} rounds",
+    ];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&ROUNDS];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-type Parsed = ([bool; 512], State);
-
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let (mapping, pattern) =
-        split2(input, "\n\n").ok_or(color_eyre::eyre::eyre!("No empty line"))?;
-    let mapping: ArrayVec<bool, 512> = mapping.trim().bytes().map(|b| b == b'#').collect();
-    let pattern = pattern
-        .lines()
-        .enumerate()
-        .map(|(l, line)| {
-            line.trim()
-                .bytes()
-                .enumerate()
-                .filter(|(_, i)| *i == b'#')
-                .map(move |(c, _)| (c as i64, l as i64))
-        })
-        .flatten()
-        .collect();
-
-    Ok((
-        mapping
-            .into_inner()
-            .map_err(|_| color_eyre::eyre::eyre!("Mapping is not 512 characters"))?,
-        State::new(pattern),
-    ))
+pub type Parsed = ([bool; 512], State);
+
+/// Pixels of a line, as whether they are lit
+fn pixels<'i>(
+    input: &'i str,
+    line: &'i str,
+) -> impl Iterator<Item = Result<bool, ParseError>> + 'i {
+    line.char_indices().map(move |(i, c)| match c {
+        '#' => Ok(true),
+        '.' => Ok(false),
+        _ => Err(ParseError::new(
+            input,
+            &line[i..i + c.len_utf8()],
+            "Expected `#` or `.`",
+        )),
+    })
+}
+
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (mapping, pattern) = parse::split(input, input, "\n\n")?;
+    let mapping = mapping.trim();
+    let algorithm = pixels(input, mapping).collect::<Result<Vec<_>, _>>()?;
+    let algorithm: [bool; 512] = algorithm.try_into().map_err(|algorithm: Vec<_>| {
+        ParseError::new(
+            input,
+            mapping,
+            format!(
+                "The algorithm has {} pixels instead of 512",
+                algorithm.len()
+            ),
+        )
+    })?;
+
+    let mut lit = HashSet::new();
+    for (l, line) in pattern.lines().enumerate() {
+        for (c, pixel) in pixels(input, line.trim()).enumerate() {
+            if pixel? {
+                lit.insert((c as i64, l as i64));
+            }
+        }
+    }
+
+    Ok((algorithm, State::new(lit)))
 }
 
 fn neighbours(x: i64, y: i64) -> [(i64, i64); 9] {
@@ -50,7 +92,8 @@ fn neighbours(x: i64, y: i64) -> [(i64, i64); 9] {
     ]
 }
 
-pub(crate) struct State {
+#[derive(Clone)]
+pub struct State {
     inverted: bool,
     coords: HashSet<(i64, i64)>,
     bounding: BoundingBox,
@@ -64,6 +107,20 @@ struct BoundingBox {
     max_y: i64,
 }
 
+impl BoundingBox {
+    /// The box of an image without lit pixels is empty
+    fn size(&self) -> (usize, usize) {
+        let size = |min: i64, max: i64| {
+            if min <= max {
+                (max - min + 1) as usize
+            } else {
+                0
+            }
+        };
+        (size(self.min_x, self.max_x), size(self.min_y, self.max_y))
+    }
+}
+
 impl State {
     fn new(coords: HashSet<(i64, i64)>) -> Self {
         Self {
@@ -117,32 +174,56 @@ impl State {
         new_bounding.min_y -= 1;
         new_bounding.max_y += 1;
 
-        Self {
+        let state = Self {
             inverted: invert_mapping,
             coords: new_coords,
             bounding: new_bounding,
-        }
+        };
+        visualize::show(|| state.frame());
+        state
     }
 
-    /* fn print(&self) {
+    fn is_lit(&self, x: i64, y: i64) -> bool {
+        self.coords.contains(&(x, y)) ^ self.inverted
+    }
+
+    /// The pixels of the bounding box, the ones outside of it are all like its border
+    fn frame(&self) -> Frame {
         let b = self.bounding;
-        let (contained, not_contained) = if self.inverted {
-            ('.', '#')
-        } else {
-            ('#', '.')
+        let (width, height) = b.size();
+        let mut frame = Frame::new(width, height);
+        for y in b.min_y..=b.max_y {
+            for x in b.min_x..=b.max_x {
+                let cell = if self.is_lit(x, y) {
+                    Cell::styled('#', Colour::Yellow.bold())
+                } else {
+                    Cell::styled('.', Style::new().dimmed())
+                };
+                frame.set((x - b.min_x) as usize, (y - b.min_y) as usize, cell);
+            }
+        }
+        let lit = match self.amount_lit() {
+            Some(lit) => lit.to_string(),
+            None => "infinitely many".into(),
         };
+        frame.caption(format!("{} pixels are lit", lit))
+    }
 
-        for y in (b.min_y - 2)..=(b.max_y + 2) {
-            for x in (b.min_x - 2)..=(b.max_x + 2) {
-                if self.coords.contains(&(x, y)) {
-                    print!("{}", contained)
-                } else {
-                    print!("{}", not_contained)
+    /// The pixels of the bounding box, as [`State::frame`]
+    fn picture(&self) -> Picture {
+        let b = self.bounding;
+        let (width, height) = b.size();
+        let mut picture = Picture::new(width, height);
+        for y in b.min_y..=b.max_y {
+            for x in b.min_x..=b.max_x {
+                if self.is_lit(x, y) {
+                    let (px, py) = ((x - b.min_x) as usize, (y - b.min_y) as usize);
+                    picture.set(px, py, Paint::Level(1.));
                 }
             }
-            println!()
         }
-    } */
+        picture
+    }
 
     fn amount_lit(&self) -> Option<usize> {
         if self.inverted {
@@ -153,22 +234,26 @@ impl State {
     }
 }
 
-pub(crate) fn part1((mapping, state): Parsed) -> EyreResult<usize> {
-    // state.print();
-
-    let state = state.step(&mapping);
-    // state.print();
-
-    let state = state.step(&mapping);
-    // state.print();
+pub fn part1((mapping, mut state): Parsed) -> EyreResult<usize> {
+    for _ in 0..ROUNDS.get(Part::Part1)? {
+        cancel::check()?;
+        state = state.step(&mapping);
+    }
+    picture::draw(|| state.picture());
 
-    Ok(state.amount_lit().unwrap())
+    state
+        .amount_lit()
+        .ok_or_else(|| eyre::eyre!("An infinite number of pixels are lit"))
 }
 
-pub(crate) fn part2((mapping, mut state): Parsed) -> EyreResult<usize> {
-    for _ in 0..50 {
+pub fn part2((mapping, mut state): Parsed) -> EyreResult<usize> {
+    for _ in 0..ROUNDS.get(Part::Part2)? {
+        cancel::check()?;
         state = state.step(&mapping);
     }
+    picture::draw(|| state.picture());
 
-    Ok(state.amount_lit().unwrap())
+    state
+        .amount_lit()
+        .ok_or_else(|| eyre::eyre!("An infinite number of pixels are lit"))
 }
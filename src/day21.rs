@@ -1,14 +1,36 @@
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    cancel, forward_solution,
+    params::{AnyParam, Param},
+    parse::{self, ParseError},
+    solution::Solution,
+    EyreResult, Part,
+};
+use color_eyre::eyre;
 use std::collections::HashMap;
 
-day! {
-    parser,
-    part1 => "Score with deterministic dice: {}",
-    part2 => "Wins with dirac dice: {}",
+pub struct Day21;
+
+pub const SCORE: Param<u64> =
+    Param::per_part("score", "Score a player must reach to win", 1000, 21);
+
+impl Solution for Day21 {
+    const DAY: usize = 21;
+    const TITLE: &'static str = "Dirac Dice";
+    const MESSAGES: [&'static str; 2] = [
+        "Score with deterministic dice: {}",
+        "Wins with dirac dice: {}",
+    ];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&SCORE];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-#[derive(Debug)]
-pub(crate) struct State {
+#[derive(Debug, Clone)]
+pub struct State {
     die: u64,
     total_rolls: u64,
     player1: u8,
@@ -43,23 +65,23 @@ impl State {
         self.score2 += next + 1;
     }
 
-    fn win1(&self) -> bool {
-        self.score1 >= 1000
+    fn win1(&self, target: u64) -> bool {
+        self.score1 >= target
     }
 
-    fn win2(&self) -> bool {
-        self.score2 >= 1000
+    fn win2(&self, target: u64) -> bool {
+        self.score2 >= target
     }
 
-    fn play(&mut self) -> u8 {
+    fn play(&mut self, target: u64) -> u8 {
         loop {
             self.play1();
-            if self.win1() {
+            if self.win1(target) {
                 return 1;
             }
 
             self.play2();
-            if self.win2() {
+            if self.win2(target) {
                 return 2;
             }
         }
@@ -116,29 +138,56 @@ mod test {
         assert_play!(state, 1, 6, 26);
         assert_play!(state, 2, 6, 22);
     }
-}
 
-type Parsed = State;
+    #[test]
+    fn dirac_overflow() {
+        use super::DiracState;
+
+        let mut state = null_state();
+        state.player1 = 3;
+        state.player2 = 7;
+        let mut dirac = DiracState::new(state.clone(), 21);
+        dirac.run().unwrap();
+        assert_eq!(
+            dirac.count_wins().unwrap(),
+            (444356092776315, 341960390180808)
+        );
+        let mut dirac = DiracState::new(state, 40);
+        let err = dirac.run().and_then(|_| dirac.count_wins()).unwrap_err();
+        assert_eq!(err.to_string(), "The number of universes overflows");
+    }
+}
 
-fn parse_player(p: &str) -> EyreResult<u8> {
-    let (_, p) = split2(p, ":").ok_or(color_eyre::eyre::eyre!("Malformed player"))?;
-    Ok(p.trim().parse()?)
+pub type Parsed = State;
+
+/// Starting position of a player, from 0 to 9
+fn parse_player(input: &str, p: &str) -> Result<u8, ParseError> {
+    let (_, position) = parse::split(input, p, ":")?;
+    let position = position.trim();
+    match parse::token(input, position)? {
+        start @ 1..=10 => Ok(start - 1),
+        _ => Err(ParseError::new(
+            input,
+            position,
+            "Positions are between 1 and 10",
+        )),
+    }
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let (p1, p2) = split2(input, "\n").ok_or(color_eyre::eyre::eyre!("no newline"))?;
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (p1, p2) = parse::split(input, input.trim_end(), "\n")?;
     Ok(State {
         total_rolls: 0,
         die: 0,
-        player1: parse_player(p1)? - 1,
-        player2: parse_player(p2)? - 1,
+        player1: parse_player(input, p1)?,
+        player2: parse_player(input, p2)?,
         score1: 0,
         score2: 0,
     })
 }
 
-pub(crate) fn part1(mut state: Parsed) -> EyreResult<u64> {
-    let winner = state.play();
+pub fn part1(mut state: Parsed) -> EyreResult<u64> {
+    let winner = state.play(SCORE.get(Part::Part1)?);
     Ok(if winner == 1 {
         state.score2 * state.total_rolls
     } else {
@@ -148,8 +197,8 @@ pub(crate) fn part1(mut state: Parsed) -> EyreResult<u64> {
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Situation {
-    score1: u8,
-    score2: u8,
+    score1: u16,
+    score2: u16,
     pos1: u8,
     pos2: u8,
 }
@@ -161,23 +210,23 @@ fn score_next(die: u8, pos: u8) -> (u8, u8) {
 }
 
 impl Situation {
-    fn is_win(&self) -> bool {
-        self.win1() || self.win2()
+    fn is_win(&self, target: u16) -> bool {
+        self.win1(target) || self.win2(target)
     }
 
-    fn win1(&self) -> bool {
-        self.score1 >= 21
+    fn win1(&self, target: u16) -> bool {
+        self.score1 >= target
     }
 
-    fn win2(&self) -> bool {
-        self.score2 >= 21
+    fn win2(&self, target: u16) -> bool {
+        self.score2 >= target
     }
 
     fn play1(self, roll: u8) -> Self {
         let (next, score) = score_next(roll, self.pos1);
         let mut new = self;
         new.pos1 = next;
-        new.score1 += score;
+        new.score1 += score as u16;
         new
     }
 
@@ -197,7 +246,7 @@ impl Situation {
         let (next, score) = score_next(roll, self.pos2);
         let mut new = self;
         new.pos2 = next;
-        new.score2 += score;
+        new.score2 += score as u16;
         new
     }
 
@@ -214,12 +263,17 @@ impl Situation {
     }
 }
 
+fn overflow() -> color_eyre::Report {
+    eyre::eyre!("The number of universes overflows")
+}
+
 struct DiracState {
     situations: HashMap<Situation, usize>,
+    target: u16,
 }
 
 impl DiracState {
-    fn new(initial: State) -> Self {
+    fn new(initial: State, target: u16) -> Self {
         let mut situations = HashMap::new();
 
         situations.insert(
@@ -232,17 +286,22 @@ impl DiracState {
             1,
         );
 
-        Self { situations }
+        Self { situations, target }
     }
 
-    fn step(&mut self, player1: bool) -> bool {
+    fn step(&mut self, player1: bool) -> EyreResult<bool> {
         let mut new_situations = HashMap::with_capacity(self.situations.len() * 3);
+        let mut add = |situation, count: usize| -> EyreResult<()> {
+            let total: &mut usize = new_situations.entry(situation).or_insert(0);
+            *total = total.checked_add(count).ok_or_else(overflow)?;
+            Ok(())
+        };
 
         let mut all_win = true;
 
         for (&situation, &count) in self.situations.iter() {
-            if situation.is_win() {
-                *new_situations.entry(situation).or_insert(0) += count;
+            if situation.is_win(self.target) {
+                add(situation, count)?;
             } else {
                 let situations = if player1 {
                     situation.dirac1()
@@ -251,7 +310,7 @@ impl DiracState {
                 };
 
                 for (situation, repeat) in situations {
-                    *new_situations.entry(situation).or_insert(0) += repeat * count;
+                    add(situation, repeat.checked_mul(count).ok_or_else(overflow)?)?;
                 }
 
                 all_win = false;
@@ -259,38 +318,50 @@ impl DiracState {
         }
 
         self.situations = new_situations;
-        all_win
+        Ok(all_win)
     }
 
-    fn run(&mut self) {
+    fn run(&mut self) -> EyreResult<()> {
         let mut player1 = true;
         loop {
-            if self.step(player1) {
-                return;
+            cancel::check()?;
+            if self.step(player1)? {
+                return Ok(());
             }
 
             player1 = !player1;
         }
     }
 
-    fn count_wins(&self) -> (usize, usize) {
+    fn count_wins(&self) -> EyreResult<(usize, usize)> {
         self.situations
             .iter()
-            .fold((0, 0), |(win1, win2), (situation, count)| {
-                assert!(situation.is_win());
-                if situation.win1() {
-                    assert!(!situation.win2());
-                    (win1 + count, win2)
+            .try_fold((0usize, 0usize), |(win1, win2), (situation, &count)| {
+                assert!(situation.is_win(self.target));
+                if situation.win1(self.target) {
+                    assert!(!situation.win2(self.target));
+                    Some((win1.checked_add(count)?, win2))
                 } else {
-                    (win1, win2 + count)
+                    Some((win1, win2.checked_add(count)?))
                 }
             })
+            .ok_or_else(overflow)
     }
 }
 
-pub(crate) fn part2(state: Parsed) -> EyreResult<usize> {
-    let mut dirac_state = DiracState::new(state);
-    dirac_state.run();
-    let (win1, win2) = dirac_state.count_wins();
+pub fn part2(state: Parsed) -> EyreResult<usize> {
+    // A turn adds at most 10 to the scores, which must stay in a u16
+    let max = u16::MAX - 10;
+    let target = match SCORE.get(Part::Part2)? {
+        target @ 1.. if target <= max as u64 => target as u16,
+        target => eyre::bail!(
+            "Games with dirac dice are played to a score between 1 and {}, not {}",
+            max,
+            target
+        ),
+    };
+    let mut dirac_state = DiracState::new(state, target);
+    dirac_state.run()?;
+    let (win1, win2) = dirac_state.count_wins()?;
     Ok(std::cmp::max(win1, win2))
 }
@@ -1,22 +1,48 @@
 use std::ops::RangeInclusive;
 
 use arrayvec::ArrayVec;
+use color_eyre::eyre;
 use itertools::iproduct;
 
-use crate::{day, utils::split2, EyreResult};
-
-day! {
-    parser,
-    part1 => "There are {} cubes for startup",
-    part2 => "There are {} cubes in total",
+use crate::{
+    cancel::{self, Cancelled},
+    forward_solution,
+    params::{AnyParam, Param},
+    parse::{self, ParseError},
+    solution::Solution,
+    EyreResult, Part,
+};
+
+pub struct Day22;
+
+pub const REGION: Param<i64> = Param::new(
+    "region",
+    "Distance to the center of the cubes considered for startup",
+    50,
+);
+
+impl Solution for Day22 {
+    const DAY: usize = 22;
+    const TITLE: &'static str = "Reactor Reboot";
+    const MESSAGES: [&'static str; 2] = [
+        "There are {} cubes for startup",
+        "There are {} cubes in total",
+    ];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&REGION];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    forward_solution!();
 }
 
 fn range_overlap(a: &RangeInclusive<i64>, b: &RangeInclusive<i64>) -> RangeInclusive<i64> {
     std::cmp::max(*a.start(), *b.start())..=std::cmp::min(*a.end(), *b.end())
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct CubeRange {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CubeRange {
     x: RangeInclusive<i64>,
     y: RangeInclusive<i64>,
     z: RangeInclusive<i64>,
@@ -34,9 +60,13 @@ fn interior(
 }
 
 impl CubeRange {
-    fn intersects(&self, other: &Self) -> bool {
+    pub fn new(x: RangeInclusive<i64>, y: RangeInclusive<i64>, z: RangeInclusive<i64>) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
         let inter = self.intersection(other);
-        !inter.empty()
+        !inter.is_empty()
     }
 
     fn split(&self, negative: &Self) -> ArrayVec<CubeRange, { 27 - 1 }> {
@@ -48,11 +78,11 @@ impl CubeRange {
 
         iproduct!(x, y, z)
             .map(|(x, y, z)| CubeRange { x, y, z })
-            .filter(|r| !r.empty() && r != &intersection)
+            .filter(|r| !r.is_empty() && r != &intersection)
             .collect()
     }
 
-    fn intersection(&self, other: &Self) -> CubeRange {
+    pub fn intersection(&self, other: &Self) -> CubeRange {
         Self {
             x: range_overlap(&self.x, &other.x),
             y: range_overlap(&self.y, &other.y),
@@ -60,12 +90,13 @@ impl CubeRange {
         }
     }
 
-    fn empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.x.is_empty() || self.y.is_empty() || self.z.is_empty()
     }
 
-    fn count(&self) -> i64 {
-        if self.empty() {
+    /// Number of cubes in the range
+    pub fn count(&self) -> i64 {
+        if self.is_empty() {
             0
         } else {
             (self.x.end() - self.x.start() + 1).abs()
@@ -119,13 +150,15 @@ fn cut_ranges(ranges: &mut Vec<CubeRange>, negative: CubeRange) {
     }
 }
 
-fn accumulate_ranges<I>(ranges: I) -> Vec<CubeRange>
+/// Applies the reboot steps, giving disjoint ranges of the cubes that are on
+pub fn accumulate_ranges<I>(ranges: I) -> Result<Vec<CubeRange>, Cancelled>
 where
     I: Iterator<Item = (bool, CubeRange)>,
 {
     let mut total_ranges: Vec<CubeRange> = Vec::new();
 
     for (on, range) in ranges {
+        cancel::check()?;
         if on {
             append_ranges(&mut total_ranges, range);
         } else {
@@ -133,7 +166,7 @@ where
         }
     }
 
-    total_ranges
+    Ok(total_ranges)
 }
 
 #[cfg(test)]
@@ -207,50 +240,83 @@ mod test {
 
         assert_eq!(sum_range(&parts), 27 + 19 - 8);
     }
+
+    #[test]
+    fn region() {
+        use super::{parser, part1};
+        use crate::params::{self, parse_override, Overrides};
+
+        let ranges = parser("on x=-20..20,y=-20..20,z=-20..20").unwrap();
+        let region = |size: &str| {
+            let overrides: Overrides =
+                [parse_override(&format!("region={}", size)).unwrap()].into();
+            params::scope(&overrides, || part1(ranges.clone()))
+        };
+        assert_eq!(region("1").unwrap(), 27);
+        assert_eq!(region("50").unwrap(), 41 * 41 * 41);
+        assert!(region("-1").is_err());
+    }
 }
 
-type Parsed = Vec<(bool, CubeRange)>;
+pub type Parsed = Vec<(bool, CubeRange)>;
 
-fn parse_range(range: &str) -> EyreResult<RangeInclusive<i64>> {
-    let (_, range) = split2(range, "=").ok_or(color_eyre::eyre::eyre!("Malformed range"))?;
-    let (start, end) = split2(range, "..").ok_or(color_eyre::eyre::eyre!("Malformed range"))?;
-    Ok(start.parse()?..=end.parse()?)
+fn parse_range(input: &str, range: &str) -> Result<RangeInclusive<i64>, ParseError> {
+    let (_, bounds) = parse::split(input, range, "=")?;
+    let (start, end) = parse::split(input, bounds, "..")?;
+    let (start, end) = (parse::token(input, start)?, parse::token(input, end)?);
+    if start > end {
+        return Err(ParseError::new(input, bounds, "The range is reversed"));
+    }
+    Ok(start..=end)
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input
         .lines()
         .map(|l| -> EyreResult<_> {
-            let (state, ranges) =
-                split2(l.trim(), " ").ok_or(color_eyre::eyre::eyre!("Malformed range"))?;
-            let (x, yz) = split2(ranges, ",").ok_or(color_eyre::eyre::eyre!("Malformed ranges"))?;
-            let (y, z) = split2(yz, ",").ok_or(color_eyre::eyre::eyre!("Malformed ranges"))?;
+            let (state, ranges) = parse::split(input, l.trim(), " ")?;
+            let on = match state {
+                "on" => true,
+                "off" => false,
+                _ => return Err(ParseError::new(input, state, "Expected `on` or `off`").into()),
+            };
+            let (x, yz) = parse::split(input, ranges, ",")?;
+            let (y, z) = parse::split(input, yz, ",")?;
             Ok((
-                state == "on",
+                on,
                 CubeRange {
-                    x: parse_range(x)?,
-                    y: parse_range(y)?,
-                    z: parse_range(z)?,
+                    x: parse_range(input, x)?,
+                    y: parse_range(input, y)?,
+                    z: parse_range(input, z)?,
                 },
             ))
         })
         .collect()
 }
 
-pub(crate) fn part1(ranges: Parsed) -> EyreResult<i64> {
-    let ranges = accumulate_ranges(ranges.into_iter().filter(|(_, r)| {
-        r.intersects(&CubeRange {
-            x: -50..=50,
-            y: -50..=50,
-            z: -50..=50,
-        })
-    }));
+pub fn part1(ranges: Parsed) -> EyreResult<i64> {
+    let region = REGION.get(Part::Part1)?;
+    if region < 0 {
+        eyre::bail!("The region can't have a negative size, got {}", region);
+    }
+    let region = CubeRange {
+        x: -region..=region,
+        y: -region..=region,
+        z: -region..=region,
+    };
+    // Only the cubes inside of the region are counted
+    let ranges = accumulate_ranges(
+        ranges
+            .into_iter()
+            .map(|(on, r)| (on, r.intersection(&region)))
+            .filter(|(_, r)| !r.is_empty()),
+    )?;
 
     Ok(sum_range(&ranges))
 }
 
-pub(crate) fn part2(ranges: Parsed) -> EyreResult<i64> {
-    let ranges = accumulate_ranges(ranges.into_iter());
+pub fn part2(ranges: Parsed) -> EyreResult<i64> {
+    let ranges = accumulate_ranges(ranges.into_iter())?;
 
     Ok(sum_range(&ranges))
 }
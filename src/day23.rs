@@ -1,11 +1,27 @@
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
-use crate::{day, EyreResult};
-
-day! {
-    parser,
-    part1 => "Path score is {}",
-    part2 => "Path score is {}",
+use crate::{
+    cancel, forward_solution,
+    parse::ParseError,
+    solution::Solution,
+    visualize::{self, Cell, Frame},
+    EyreResult,
+};
+use ansi_term::{Colour, Style};
+use color_eyre::eyre;
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: usize = 23;
+    const TITLE: &'static str = "Amphipod";
+    const MESSAGES: [&'static str; 2] = ["Path score is {}", "Path score is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
@@ -50,7 +66,7 @@ impl Amphipod {
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
-pub(crate) struct Board<const N: usize> {
+pub struct Board<const N: usize> {
     hall: [Amphipod; 11],
     a_room: [Amphipod; N],
     b_room: [Amphipod; N],
@@ -74,7 +90,7 @@ impl<const N: usize> Default for Board<N> {
     }
 }
 
-fn a_star<const N: usize>(start: Board<N>) -> (u64, Vec<Board<N>>) {
+fn a_star<const N: usize>(start: Board<N>) -> EyreResult<(u64, Vec<Board<N>>)> {
     fn reconstruct_path<const N: usize>(
         come_from: &HashMap<Board<N>, Board<N>>,
         mut current: Board<N>,
@@ -135,13 +151,14 @@ fn a_star<const N: usize>(start: Board<N>) -> (u64, Vec<Board<N>>) {
     f_scores.insert(start, start.heuristic());
 
     while !paths.is_empty() {
+        cancel::check()?;
         let best = paths.pop().unwrap();
 
         if best.board == goal {
-            return (
+            return Ok((
                 *g_scores.get(&best.board).unwrap(),
                 reconstruct_path(&come_from, goal),
-            );
+            ));
         }
 
         if let Some(&f) = f_scores.get(&best.board) {
@@ -173,10 +190,15 @@ fn a_star<const N: usize>(start: Board<N>) -> (u64, Vec<Board<N>>) {
         }
     }
 
-    panic!("No path to goal")
+    Err(eyre::eyre!("No path to goal"))
 }
 
 impl<const N: usize> Board<N> {
+    /// Cheapest energy needed to organize the amphipods, along with the boards on the way
+    pub fn solve(self) -> EyreResult<(u64, Vec<Board<N>>)> {
+        a_star(self)
+    }
+
     fn rooms(&self) -> [([Amphipod; N], Amphipod, usize); 4] {
         [
             (self.a_room, Amphipod::A, 2),
@@ -357,6 +379,21 @@ impl<const N: usize> Board<N> {
     }
 }
 
+impl Board<2> {
+    /// Inserts the two folded lines of the diagram in the rooms
+    pub fn unfold(self) -> Board<4> {
+        use Amphipod::*;
+
+        Board {
+            hall: self.hall,
+            a_room: [self.a_room[0], D, D, self.a_room[1]],
+            b_room: [self.b_room[0], C, B, self.b_room[1]],
+            c_room: [self.c_room[0], B, A, self.c_room[1]],
+            d_room: [self.d_room[0], A, C, self.d_room[1]],
+        }
+    }
+}
+
 impl<const N: usize> std::fmt::Display for Board<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for _ in 0..13 {
@@ -385,65 +422,108 @@ impl<const N: usize> std::fmt::Display for Board<N> {
     }
 }
 
-type Parsed = Board<2>;
+/// Shows the boards of the path one after the other, with the amphipods coloured by kind
+fn show_path<const N: usize>(path: &[Board<N>]) {
+    for (step, board) in path.iter().enumerate() {
+        visualize::show(|| {
+            let mut frame = Frame::from_text(&board.to_string());
+            for y in 0..frame.height() {
+                for x in 0..frame.width() {
+                    let glyph = frame.get(x, y).glyph;
+                    let style = match glyph {
+                        'A' => Colour::Red.bold(),
+                        'B' => Colour::Green.bold(),
+                        'C' => Colour::Blue.bold(),
+                        'D' => Colour::Yellow.bold(),
+                        _ => Style::new().dimmed(),
+                    };
+                    frame.set(x, y, Cell::styled(glyph, style));
+                }
+            }
+            frame.caption(format!("Move {}/{}", step, path.len() - 1))
+        });
+    }
+}
+
+pub type Parsed = Board<2>;
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     let mut lines = input.lines().skip(2);
     let mut board = Board::default();
-    let amphi_filter = |a| match a {
-        b'A' => Some(Amphipod::A),
-        b'B' => Some(Amphipod::B),
-        b'C' => Some(Amphipod::C),
-        b'D' => Some(Amphipod::D),
-        _ => None,
-    };
 
-    let mut add_lines = |i| {
-        for (idx, amphi) in lines
+    for i in 0..2 {
+        let line = lines
             .next()
-            .unwrap()
-            .bytes()
-            .filter_map(amphi_filter)
-            .enumerate()
-        {
-            match idx {
-                0 => board.a_room[i] = amphi,
-                1 => board.b_room[i] = amphi,
-                2 => board.c_room[i] = amphi,
-                3 => board.d_room[i] = amphi,
-                _ => unreachable!(),
-            }
+            .ok_or_else(|| ParseError::new(input, "", "Expected two rows of amphipods"))?;
+        let mut rooms = [
+            &mut board.a_room,
+            &mut board.b_room,
+            &mut board.c_room,
+            &mut board.d_room,
+        ]
+        .into_iter();
+        for (idx, c) in line.char_indices() {
+            let at = &line[idx..idx + c.len_utf8()];
+            let amphi = match c {
+                'A' => Amphipod::A,
+                'B' => Amphipod::B,
+                'C' => Amphipod::C,
+                'D' => Amphipod::D,
+                '#' | ' ' => continue,
+                _ => return Err(ParseError::new(input, at, "Expected an amphipod").into()),
+            };
+            let room = rooms
+                .next()
+                .ok_or_else(|| ParseError::new(input, at, "There are only four rooms"))?;
+            room[i] = amphi;
         }
-    };
+        if rooms.next().is_some() {
+            return Err(ParseError::new(input, line, "Expected four amphipods").into());
+        }
+    }
 
-    add_lines(0);
-    add_lines(1);
+    let rooms = [board.a_room, board.b_room, board.c_room, board.d_room];
+    for kind in [Amphipod::A, Amphipod::B, Amphipod::C, Amphipod::D] {
+        if rooms.iter().flatten().filter(|&&a| a == kind).count() != 2 {
+            return Err(ParseError::new(
+                input,
+                input.lines().nth(2).unwrap_or_default(),
+                format!("Expected two amphipods {}", kind),
+            )
+            .into());
+        }
+    }
 
     Ok(board)
 }
 
-pub(crate) fn part1(board: Parsed) -> EyreResult<u64> {
-    Ok(a_star(board).0)
+pub fn part1(board: Parsed) -> EyreResult<u64> {
+    let (score, path) = board.solve()?;
+    show_path(&path);
+    Ok(score)
 }
 
-pub(crate) fn part2(board: Parsed) -> EyreResult<u64> {
-    use Amphipod::*;
-
-    let a_room = [board.a_room[0], D, D, board.a_room[1]];
-    let b_room = [board.b_room[0], C, B, board.b_room[1]];
-    let c_room = [board.c_room[0], B, A, board.c_room[1]];
-    let d_room = [board.d_room[0], A, C, board.d_room[1]];
-
-    let board = Board {
-        hall: board.hall,
-        a_room,
-        b_room,
-        c_room,
-        d_room,
-    };
-    let (score, _) = a_star(board);
-    /* for p in path {
-        println!("STEP:\n{}", p);
-    } */
+pub fn part2(board: Parsed) -> EyreResult<u64> {
+    let (score, path) = board.unfold().solve()?;
+    show_path(&path);
     Ok(score)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Amphipod, Board};
+
+    #[test]
+    fn no_path() {
+        // With three amphipods B and one A, the room of A can never be filled
+        let board = Board::<2> {
+            a_room: [Amphipod::B, Amphipod::B],
+            b_room: [Amphipod::B, Amphipod::A],
+            c_room: [Amphipod::C, Amphipod::C],
+            d_room: [Amphipod::D, Amphipod::D],
+            ..Board::default()
+        };
+        let err = board.solve().err().unwrap().to_string();
+        assert_eq!(err, "No path to goal");
+    }
+}
@@ -1,25 +1,53 @@
-use crate::{day, EyreResult};
+use crate::{forward_solution, parse::ParseError, solution::Solution, trace, EyreResult};
+use color_eyre::eyre;
 
-day! {
-    parser,
-    part1 => "{}",
-    part2 => "{}",
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: usize = 3;
+    const TITLE: &'static str = "Binary Diagnostic";
+    const MESSAGES: [&'static str; 2] = ["Power consumption is {}", "Life support rating is {}"];
+
+    type Parsed<'i> = (usize, Vec<u16>);
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<(usize, Vec<u16>)> {
-    let len = input
-        .find('\n')
-        .ok_or(color_eyre::eyre::eyre!("number list is empty"))?;
-    Ok((
-        len,
-        input
-            .lines()
-            .map(|v| u16::from_str_radix(v.trim(), 2).map_err(Into::into))
-            .collect::<EyreResult<_>>()?,
-    ))
+pub fn parser(input: &str) -> EyreResult<(usize, Vec<u16>)> {
+    let len = input.lines().next().unwrap_or_default().trim().len();
+    if len == 0 {
+        return Err(ParseError::new(input, input, "The number list is empty").into());
+    }
+    let numbers = input
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            if let Some((i, c)) = line.char_indices().find(|&(_, c)| c != '0' && c != '1') {
+                return Err(ParseError::new(
+                    input,
+                    &line[i..i + c.len_utf8()],
+                    "Expected a binary digit",
+                ));
+            }
+            if line.len() > 16 {
+                return Err(ParseError::new(input, line, "Numbers have at most 16 bits"));
+            }
+            if line.len() != len {
+                return Err(ParseError::new(
+                    input,
+                    line,
+                    format!("Expected {} bits like the first number", len),
+                ));
+            }
+            u16::from_str_radix(line, 2).map_err(|e| ParseError::new(input, line, e))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((len, numbers))
 }
 
-pub(crate) fn part1((num_len, numbers): (usize, Vec<u16>)) -> EyreResult<String> {
+pub fn part1((num_len, numbers): (usize, Vec<u16>)) -> EyreResult<u64> {
     let (len, occurences) =
         numbers
             .iter()
@@ -34,15 +62,16 @@ pub(crate) fn part1((num_len, numbers): (usize, Vec<u16>)) -> EyreResult<String>
         gamma += ((bit > (len / 2)) as u16) << pos;
     }
     let epsilon = !gamma & !(!0 << num_len);
-    Ok(format!(
-        "Gamma is {:b} and epsilon is {:b}. Power consumption is {}",
-        gamma,
-        epsilon,
-        gamma as u64 * epsilon as u64
-    ))
+    trace!(
+        Info,
+        "rates",
+        gamma = format!("{:b}", gamma),
+        epsilon = format!("{:b}", epsilon)
+    );
+    Ok(gamma as u64 * epsilon as u64)
 }
 
-fn bit_criteria_filter(mut numbers: Vec<u16>, num_len: usize, most: bool) -> u16 {
+fn bit_criteria_filter(mut numbers: Vec<u16>, num_len: usize, most: bool) -> EyreResult<u16> {
     let mut position = num_len - 1;
     while numbers.len() > 1 {
         let ones = numbers.iter().fold(0, |occurence, number| {
@@ -55,7 +84,7 @@ fn bit_criteria_filter(mut numbers: Vec<u16>, num_len: usize, most: bool) -> u16
             if most {
                 ones > numbers.len() / 2
             } else {
-                ones < numbers.len() / 2
+                ones * 2 < numbers.len()
             }
         };
 
@@ -71,18 +100,20 @@ fn bit_criteria_filter(mut numbers: Vec<u16>, num_len: usize, most: bool) -> u16
         position -= 1;
     }
 
-    assert!(numbers.len() == 1);
-
-    numbers[0]
+    match numbers[..] {
+        [rating] => Ok(rating),
+        _ => eyre::bail!("The bit criteria did not select a single number"),
+    }
 }
 
-pub(crate) fn part2((num_len, numbers): (usize, Vec<u16>)) -> EyreResult<String> {
-    let oxygen_rating = bit_criteria_filter(numbers.clone(), num_len, true);
-    let co2_rating = bit_criteria_filter(numbers, num_len, false);
-    Ok(format!(
-        "oxygen_rating is {:b} and co2_rating is {:b}. Life support is {}",
-        oxygen_rating,
-        co2_rating,
-        oxygen_rating as u64 * co2_rating as u64
-    ))
+pub fn part2((num_len, numbers): (usize, Vec<u16>)) -> EyreResult<u64> {
+    let oxygen_rating = bit_criteria_filter(numbers.clone(), num_len, true)?;
+    let co2_rating = bit_criteria_filter(numbers, num_len, false)?;
+    trace!(
+        Info,
+        "ratings",
+        oxygen = format!("{:b}", oxygen_rating),
+        co2 = format!("{:b}", co2_rating)
+    );
+    Ok(oxygen_rating as u64 * co2_rating as u64)
 }
@@ -1,14 +1,29 @@
-use crate::{day, EyreResult};
-use arrayvec::ArrayVec;
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    trace, EyreResult,
+};
 
-day! {
-    parser,
-    part1 => "Score of the first winning board is {}",
-    part2 => "Score of the last winning board is {}"
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: usize = 4;
+    const TITLE: &'static str = "Giant Squid";
+    const MESSAGES: [&'static str; 2] = [
+        "Score of the first winning board is {}",
+        "Score of the last winning board is {}",
+    ];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-#[derive(Debug)]
-pub(crate) struct Board {
+#[derive(Debug, Clone)]
+pub struct Board {
     array: [[u64; 5]; 5],
     marked: [[bool; 5]; 5],
 }
@@ -19,15 +34,17 @@ impl Board {
             || (0..5).any(|idx| self.marked.iter().all(|line| line[idx]))
     }
 
-    fn draw(&mut self, num: u64) {
+    /// Marks `num`, returning whether it is on the board
+    fn draw(&mut self, num: u64) -> bool {
         for (num_line, marked_line) in self.array.iter().zip(self.marked.iter_mut()) {
             for (&n, marked) in num_line.iter().zip(marked_line.iter_mut()) {
                 if n == num {
                     *marked = true;
-                    return;
+                    return true;
                 }
             }
         }
+        false
     }
 
     fn score(&self) -> u64 {
@@ -44,65 +61,122 @@ impl Board {
     }
 }
 
-type Parsed = (Vec<u64>, Vec<Board>);
+pub type Parsed = (Vec<u64>, Vec<Board>);
+
+fn parse_board(input: &str, board: &str) -> Result<Board, ParseError> {
+    let mut array = [[0; 5]; 5];
+    let mut lines = board.lines();
+    for row in &mut array {
+        let line = lines
+            .next()
+            .ok_or_else(|| ParseError::new(input, &board[board.len()..], "Boards have 5 rows"))?;
+        let mut numbers = line.split_whitespace();
+        for cell in row.iter_mut() {
+            let number = numbers
+                .next()
+                .ok_or_else(|| ParseError::new(input, line, "Rows have 5 numbers"))?;
+            *cell = parse::token(input, number)?;
+        }
+        if let Some(extra) = numbers.next() {
+            return Err(ParseError::new(input, extra, "Rows have 5 numbers"));
+        }
+    }
+    if let Some(extra) = lines.next() {
+        return Err(ParseError::new(input, extra, "Boards have 5 rows"));
+    }
+
+    Ok(Board {
+        array,
+        marked: [[false; 5]; 5],
+    })
+}
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let mut input = input.split("\n\n");
-    let draw = input
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let mut chunks = input.split("\n\n");
+    let draw = chunks
         .next()
-        .ok_or(color_eyre::eyre::eyre!("First line is missing"))?
-        .split(",")
-        .map(|n| n.parse().map_err(Into::into))
-        .collect::<EyreResult<_>>()?;
-
-    let boards = input
-        .map(|board| -> EyreResult<Board> {
-            Ok(Board {
-                array: board
-                    .lines()
-                    .map(|line| {
-                        line.split_whitespace()
-                            .map(|n| n.parse().map_err(Into::into))
-                            .collect::<EyreResult<ArrayVec<u64, 5>>>()?
-                            .into_inner()
-                            .map_err(|_| color_eyre::eyre::eyre!("Lines where not 5x5"))
-                    })
-                    .collect::<EyreResult<ArrayVec<[u64; 5], 5>>>()?
-                    .into_inner()
-                    .map_err(|_| color_eyre::eyre::eyre!("Board does not have 5 rows"))?,
-                marked: [[false; 5]; 5],
-            })
-        })
-        .collect::<EyreResult<_>>()?;
+        .unwrap_or_default()
+        .split(',')
+        .map(|n| parse::token(input, n.trim()))
+        .collect::<Result<_, _>>()?;
+
+    let boards = chunks
+        .map(|board| parse_board(input, board))
+        .collect::<Result<_, _>>()?;
 
     Ok((draw, boards))
 }
 
-pub(crate) fn part1((draws, mut boards): Parsed) -> EyreResult<u64> {
+pub fn part1((draws, mut boards): Parsed) -> EyreResult<u64> {
     for draw in draws {
-        for board in &mut boards {
-            board.draw(draw);
+        trace!(Info, "draw", number = draw);
+        for (idx, board) in boards.iter_mut().enumerate() {
+            if board.draw(draw) {
+                trace!(Debug, "mark", board = idx, number = draw);
+            }
             if board.is_complete() {
+                trace!(
+                    Info,
+                    "win",
+                    board = idx,
+                    unmarked = board.score(),
+                    draw = draw
+                );
                 return Ok(board.score() * draw);
             }
         }
     }
-    unreachable!("no board wins")
+    color_eyre::eyre::bail!("No board wins")
 }
 
-pub(crate) fn part2((draws, mut boards): Parsed) -> EyreResult<u64> {
+pub fn part2((draws, boards): Parsed) -> EyreResult<u64> {
+    // The boards keep their index in the input as the complete ones are removed
+    let mut boards: Vec<_> = boards.into_iter().enumerate().collect();
     for draw in draws {
+        trace!(Info, "draw", number = draw, playing = boards.len());
         let mut remaining_win = boards.len();
-        for board in &mut boards {
-            board.draw(draw);
+        for (idx, board) in &mut boards {
+            if board.draw(draw) {
+                trace!(Debug, "mark", board = idx, number = draw);
+            }
             if board.is_complete() {
+                trace!(
+                    Info,
+                    "win",
+                    board = idx,
+                    unmarked = board.score(),
+                    draw = draw
+                );
                 remaining_win -= 1;
                 if remaining_win == 0 {
                     return Ok(board.score() * draw);
                 }
             }
         }
-        boards.retain(|b| !b.is_complete())
+        boards.retain(|(_, b)| !b.is_complete())
+    }
+    color_eyre::eyre::bail!("No board wins")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parser, part2};
+    use crate::trace;
+
+    #[test]
+    fn last_winner() {
+        let input = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/day4"),
+        )
+        .unwrap();
+        let score = trace::scope(1, || part2(parser(&input).unwrap())).unwrap();
+        assert_eq!(score, 1924);
+        // The boards keep their index in the input once the others won
+        let wins: Vec<_> = trace::captured()
+            .lines()
+            .filter(|line| line.starts_with("win"))
+            .map(String::from)
+            .collect();
+        assert_eq!(wins.last().unwrap(), "win board=1 unmarked=148 draw=13");
     }
-    unreachable!("no board wins")
 }
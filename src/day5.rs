@@ -1,14 +1,30 @@
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    picture::{self, Paint, Picture},
+    solution::Solution,
+    EyreResult,
+};
 use num::integer::gcd;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-day! {
-    parser,
-    part1 => "Overlapping point count: {}",
-    part2 => "Overlapping point count: {}"
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: usize = 5;
+    const TITLE: &'static str = "Hydrothermal Venture";
+    const MESSAGES: [&'static str; 2] =
+        ["Overlapping point count: {}", "Overlapping point count: {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-pub(crate) struct Line {
+#[derive(Clone)]
+pub struct Line {
     start: (i64, i64),
     end: (i64, i64),
 }
@@ -30,7 +46,8 @@ impl Line {
 
     fn points(&self) -> impl Iterator<Item = (i64, i64)> {
         let mut director = (self.end.0 - self.start.0, self.end.1 - self.start.1);
-        let div = gcd(director.0, director.1);
+        // A line can be a single point
+        let div = gcd(director.0, director.1).max(1);
         director.0 /= div;
         director.1 /= div;
         LineIterator {
@@ -44,7 +61,7 @@ impl Line {
 
 #[derive(Debug)]
 struct LineIterator {
-   current: (i64, i64),
+    current: (i64, i64),
     end: (i64, i64),
     director: (i64, i64),
     done: bool,
@@ -70,28 +87,79 @@ impl Iterator for LineIterator {
     }
 }
 
-fn parse_tuple(s: &str) -> EyreResult<(i64, i64)> {
-    let (x, y) = split2(s, ",").ok_or(color_eyre::eyre::eyre!("Invalid coords: {}", s))?;
-    Ok((x.parse()?, y.parse()?))
+fn parse_tuple(input: &str, s: &str) -> Result<(i64, i64), ParseError> {
+    let (x, y) = parse::split(input, s, ",")?;
+    Ok((parse::token(input, x)?, parse::token(input, y)?))
 }
 
-type Parsed = Vec<Line>;
+pub type Parsed = Vec<Line>;
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input
         .lines()
         .map(|line| -> EyreResult<_> {
-            let (start, end) = split2(line, " -> ")
-                .ok_or(color_eyre::eyre::eyre!("Line format is invalid: {}", line))?;
-            Ok(Line {
-                start: parse_tuple(start)?,
-                end: parse_tuple(end)?,
-            })
+            let (start, end) = parse::split(input, line, " -> ")?;
+            let segment = Line {
+                start: parse_tuple(input, start)?,
+                end: parse_tuple(input, end)?,
+            };
+            let (dx, dy) = (
+                segment.end.0 - segment.start.0,
+                segment.end.1 - segment.start.1,
+            );
+            if !segment.is_straight() && dx.abs() != dy.abs() {
+                return Err(ParseError::new(
+                    input,
+                    line,
+                    "Lines are horizontal, vertical or diagonal",
+                )
+                .into());
+            }
+            Ok(segment)
         })
         .collect()
 }
 
-pub(crate) fn part1(lines: Parsed) -> EyreResult<usize> {
+/// How many vents cover each point, with the vents drawn as lines for vector formats
+fn heat_map(lines: &[&Line]) -> Picture {
+    let points = || lines.iter().flat_map(|line| [line.start, line.end]);
+    let min = |coord: fn((i64, i64)) -> i64| points().map(coord).min().unwrap_or(0);
+    let max = |coord: fn((i64, i64)) -> i64| points().map(coord).max().unwrap_or(-1);
+    let (x0, y0) = (min(|p| p.0), min(|p| p.1));
+    let at = |(x, y): (i64, i64)| ((x - x0) as usize, (y - y0) as usize);
+
+    let mut counts = HashMap::new();
+    for line in lines {
+        for point in line.points() {
+            *counts.entry(point).or_insert(0) += 1;
+        }
+    }
+    let most = counts.values().copied().max().unwrap_or(1);
+
+    let mut picture = Picture::new(
+        (max(|p| p.0) - x0 + 1) as usize,
+        (max(|p| p.1) - y0 + 1) as usize,
+    );
+    for (point, count) in counts {
+        let (x, y) = at(point);
+        picture.set(x, y, Paint::Level(count as f32 / most as f32));
+    }
+    for line in lines {
+        picture.line(at(line.start), at(line.end), Paint::Level(1.));
+    }
+    picture
+}
+
+pub fn part1(lines: Parsed) -> EyreResult<usize> {
+    picture::draw(|| {
+        heat_map(
+            &lines
+                .iter()
+                .filter(|line| line.is_straight())
+                .collect::<Vec<_>>(),
+        )
+    });
+
     let mut present = HashSet::new();
     let mut overlap = HashSet::new();
     lines
@@ -107,7 +175,8 @@ pub(crate) fn part1(lines: Parsed) -> EyreResult<usize> {
     Ok(overlap.len())
 }
 
-pub(crate) fn part2(lines: Parsed) -> EyreResult<usize> {
+pub fn part2(lines: Parsed) -> EyreResult<usize> {
+    picture::draw(|| heat_map(&lines.iter().collect::<Vec<_>>()));
     let mut present = HashSet::new();
     let mut overlap = HashSet::new();
     lines.iter().for_each(|line| {
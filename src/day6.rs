@@ -1,14 +1,36 @@
-use crate::{day, EyreResult};
+use color_eyre::eyre;
 
-day! {
-    parser,
-    part1 => "After 80 days there are {} fishes",
-    part2 => "After 256 days there are {} fishes"
+use crate::{
+    forward_solution,
+    params::{AnyParam, Param},
+    parse::ParseError,
+    solution::Solution,
+    EyreResult, Part,
+};
+
+pub struct Day6;
+
+pub const DAYS: Param<usize> = Param::per_part("days", "Days the fishes reproduce for", 80, 256);
+
+impl Solution for Day6 {
+    const DAY: usize = 6;
+    const TITLE: &'static str = "Lanternfish";
+    const MESSAGES: [&'static str; 2] = [
+        "After {days} days there are {} fishes",
+        "After {days} days there are {} fishes",
+    ];
+    const PARAMS: &'static [&'static dyn AnyParam] = &[&DAYS];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-type Parsed = [u64; 9];
+pub type Parsed = [u64; 9];
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input.split(",").try_fold([0; 9], |mut current, fish| {
         let idx = match fish.trim() {
             "0" => 0,
@@ -20,45 +42,51 @@ pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
             "6" => 6,
             "7" => 7,
             "8" => 8,
-            _ => color_eyre::eyre::bail!("No such fish: {}", fish),
+            _ => return Err(ParseError::new(input, fish.trim(), "No such fish").into()),
         };
         current[idx] += 1;
         Ok(current)
     })
 }
 
-fn step(current: [u64; 9]) -> [u64; 9] {
+/// The fishes after a day, `None` when there are too many to count
+fn step(current: [u64; 9]) -> Option<[u64; 9]> {
     let mut next = [0; 9];
     for i in 1..9 {
         next[i - 1] = current[i];
     }
-    next[6] += current[0];
-    next[8] += current[0];
-    next
+    next[6] = next[6].checked_add(current[0])?;
+    next[8] = current[0];
+    Some(next)
 }
 
-pub(crate) fn part1(mut fishes: Parsed) -> EyreResult<u64> {
-    for _ in 0..80 {
-        fishes = step(fishes)
+fn count_after(mut fishes: Parsed, days: usize) -> EyreResult<u64> {
+    let overflow = || eyre::eyre!("The number of fishes overflows after {} days", days);
+    for _ in 0..days {
+        fishes = step(fishes).ok_or_else(overflow)?;
     }
-    Ok(fishes.iter().sum())
+    fishes
+        .iter()
+        .try_fold(0u64, |total, &count| total.checked_add(count))
+        .ok_or_else(overflow)
 }
 
-pub(crate) fn part2(mut fishes: Parsed) -> EyreResult<u64> {
-    for _ in 0..256 {
-        fishes = step(fishes)
-    }
-    Ok(fishes.iter().sum())
+pub fn part1(fishes: Parsed) -> EyreResult<u64> {
+    count_after(fishes, DAYS.get(Part::Part1)?)
+}
+
+pub fn part2(fishes: Parsed) -> EyreResult<u64> {
+    count_after(fishes, DAYS.get(Part::Part2)?)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{parser, step};
+    use super::{count_after, parser, step};
 
     #[test]
     fn step_one() {
         assert_eq!(
-            step(parser("1,2,1,6,0,8").unwrap()),
+            step(parser("1,2,1,6,0,8").unwrap()).unwrap(),
             parser("0,1,0,5,6,7,8").unwrap()
         );
     }
@@ -67,11 +95,20 @@ mod test {
     fn example() {
         let mut state = parser("3,4,3,1,2").unwrap();
         for _ in 0..18 {
-            state = step(state);
+            state = step(state).unwrap();
         }
         assert_eq!(
             state,
             parser("6,0,6,4,5,6,0,1,1,2,6,0,1,1,1,2,2,3,3,4,6,7,8,8,8,8").unwrap()
         );
+        assert_eq!(
+            count_after(parser("3,4,3,1,2").unwrap(), 256).unwrap(),
+            26984457539
+        );
+        let err = count_after(parser("3,4,3,1,2").unwrap(), 1000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The number of fishes overflows after 1000 days"
+        );
     }
 }
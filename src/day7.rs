@@ -1,30 +1,38 @@
-use crate::{day, EyreResult};
+use crate::{forward_solution, parse, solution::Solution, EyreResult};
 
-day! {
-    parser,
-    part1 => "Minimum fuel is {}",
-    part2 => "Minimum fuel is {}",
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: usize = 7;
+    const TITLE: &'static str = "The Treachery of Whales";
+    const MESSAGES: [&'static str; 2] = ["Minimum fuel is {}", "Minimum fuel is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    forward_solution!();
 }
 
-type Parsed = Vec<u64>;
+pub type Parsed = Vec<u64>;
 
 fn median(input: &mut [u64]) -> u64 {
     input.sort();
     if input.len() % 2 == 0 {
-        (input[input.len() / 2] + input[(input.len() / 2) + 1]) / 2
+        (input[input.len() / 2 - 1] + input[input.len() / 2]) / 2
     } else {
-        input[(input.len() + 1) / 2]
+        input[input.len() / 2]
     }
 }
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
+pub fn parser(input: &str) -> EyreResult<Parsed> {
     input
         .split(",")
-        .map(|x| x.trim().parse().map_err(Into::into))
+        .map(|x| Ok(parse::token(input, x.trim())?))
         .collect()
 }
 
-pub(crate) fn part1(mut input: Parsed) -> EyreResult<u64> {
+pub fn part1(mut input: Parsed) -> EyreResult<u64> {
     let min_fuel = median(&mut input) as i64;
     Ok(input
         .iter()
@@ -41,7 +49,7 @@ fn total_fuel(x: u64, pos: &[u64]) -> u64 {
     pos.iter().map(|&xi| fuel(x, xi)).sum()
 }
 
-pub(crate) fn part2(input: Parsed) -> EyreResult<u64> {
+pub fn part2(input: Parsed) -> EyreResult<u64> {
     let average = (input.iter().sum::<u64>() as f64 / input.len() as f64).round() as u64;
     // We can prove that avg(x) - 0.5 < distance < avg(x) + 0.5
     // We just have to try 2 values in order to find the correct distance, but use 3 just to be
@@ -1,36 +1,73 @@
-use crate::{day, utils::split2, EyreResult};
+use crate::{
+    forward_solution,
+    parse::{self, ParseError},
+    solution::Solution,
+    EyreResult,
+};
 use arrayvec::ArrayVec;
 use color_eyre::eyre;
 
-day! {
-    parser,
-    part1 => "There are {} 1,4,7 and 8",
-    part2 => "Sum of displays is {}"
-}
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: usize = 8;
+    const TITLE: &'static str = "Seven Segment Search";
+    const MESSAGES: [&'static str; 2] = ["There are {} 1,4,7 and 8", "Sum of displays is {}"];
 
-type Parsed = Vec<([ArrayVec<u8, 7>; 10], [ArrayVec<u8, 7>; 4])>;
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
+}
 
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    fn parse_segments<const N: usize>(segments: &str) -> EyreResult<[ArrayVec<u8, 7>; N]> {
-        segments
-            .split_whitespace()
-            .map(|segment| segment.as_bytes().iter().map(|c| c - b'a').collect())
-            .collect::<ArrayVec<_, N>>()
-            .into_inner()
-            .map_err(|s| eyre::eyre!("Not enough segments: got {} needed {}", s.len(), N))
+pub type Parsed = Vec<([ArrayVec<u8, 7>; 10], [ArrayVec<u8, 7>; 4])>;
+
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    fn parse_segments<const N: usize>(
+        input: &str,
+        segments: &str,
+    ) -> Result<[ArrayVec<u8, 7>; N], ParseError> {
+        let mut digits = ArrayVec::<_, N>::new();
+        for digit in segments.split_whitespace() {
+            let mut wires = ArrayVec::new();
+            for (i, c) in digit.char_indices() {
+                let at = &digit[i..i + c.len_utf8()];
+                if !('a'..='g').contains(&c) {
+                    return Err(ParseError::new(input, at, "Segments are named a to g"));
+                }
+                let wire = c as u8 - b'a';
+                if wires.contains(&wire) {
+                    return Err(ParseError::new(input, at, "Segment is repeated"));
+                }
+                wires.push(wire);
+            }
+            digits
+                .try_push(wires)
+                .map_err(|_| ParseError::new(input, digit, format!("Expected {} digits", N)))?;
+        }
+        digits.into_inner().map_err(|digits| {
+            ParseError::new(
+                input,
+                segments,
+                format!("Expected {} digits, got {}", N, digits.len()),
+            )
+        })
     }
 
     input
         .lines()
         .map(|line| {
-            let (input, output) =
-                split2(line, " | ").ok_or(eyre::eyre!("no | in line: {:?}", line))?;
-            Ok((parse_segments(input)?, parse_segments(output)?))
+            let (patterns, output) = parse::split(input, line, " | ")?;
+            Ok((
+                parse_segments(input, patterns)?,
+                parse_segments(input, output)?,
+            ))
         })
         .collect()
 }
 
-pub(crate) fn part1(logs: Parsed) -> EyreResult<usize> {
+pub fn part1(logs: Parsed) -> EyreResult<usize> {
     Ok(logs
         .iter()
         .map(|(_, output)| {
@@ -105,26 +142,27 @@ fn recover_mapping(inputs: &[ArrayVec<u8, 7>; 10]) -> EyreResult<[ArrayVec<u8, 7
     Ok([zero, one, two, three, four, five, six, seven, eight, nine])
 }
 
-fn translate_output(reversed: &[ArrayVec<u8, 7>], output: &mut [ArrayVec<u8, 7>]) -> usize {
-    output
-        .iter_mut()
-        .map(|segment| {
-            segment.sort();
-            reversed
-                .iter()
-                .enumerate()
-                .find(|(_, s)| s == &segment)
-                .unwrap()
-                .0
-        })
-        .fold(0, |x, n| 10 * x + n)
+fn translate_output(
+    reversed: &[ArrayVec<u8, 7>],
+    output: &mut [ArrayVec<u8, 7>],
+) -> EyreResult<usize> {
+    output.iter_mut().try_fold(0, |x, segment| {
+        let pattern: String = segment.iter().map(|&wire| (b'a' + wire) as char).collect();
+        segment.sort();
+        let (n, _) = reversed
+            .iter()
+            .enumerate()
+            .find(|(_, s)| s == &segment)
+            .ok_or_else(|| eyre::eyre!("The output {} is not one of the digits", pattern))?;
+        Ok(10 * x + n)
+    })
 }
 
-pub(crate) fn part2(mut logs: Parsed) -> EyreResult<usize> {
+pub fn part2(mut logs: Parsed) -> EyreResult<usize> {
     logs.iter_mut()
         .map(|(input, ref mut output)| -> EyreResult<_> {
             let reversed = recover_mapping(&input)?;
-            Ok(translate_output(&reversed, output))
+            translate_output(&reversed, output)
         })
         .try_fold(0, |s, n| Ok(s + n?))
 }
@@ -141,8 +179,20 @@ mod test {
 
         let reversed_mapping = recover_mapping(&input).unwrap();
 
-        let output = translate_output(&reversed_mapping, &mut output);
+        let output = translate_output(&reversed_mapping, &mut output).unwrap();
 
         assert_eq!(output, 5353)
     }
+
+    #[test]
+    fn unknown_output() {
+        let input =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdaf";
+        let (input, mut output) = parser(input).unwrap().into_iter().next().unwrap();
+
+        let reversed_mapping = recover_mapping(&input).unwrap();
+        let err = translate_output(&reversed_mapping, &mut output).unwrap_err();
+
+        assert_eq!(err.to_string(), "The output cdaf is not one of the digits")
+    }
 }
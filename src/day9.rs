@@ -1,32 +1,34 @@
-use crate::{EyreResult, day};
+use crate::{
+    forward_solution, parse,
+    picture::{self, Paint, Picture},
+    solution::Solution,
+    visualize::{self, Cell, Frame},
+    EyreResult,
+};
+use ansi_term::Style;
+use color_eyre::eyre;
 use nalgebra::DMatrix;
 use std::collections::HashSet;
 
-day! {
-    parser,
-    part1 => "Risk level is {}",
-    part2 => "Multiplication of size is {}",
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: usize = 9;
+    const TITLE: &'static str = "Smoke Basin";
+    const MESSAGES: [&'static str; 2] = ["Risk level is {}", "Multiplication of size is {}"];
+
+    type Parsed<'i> = Parsed;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    forward_solution!();
 }
 
-type Parsed = DMatrix<u8>;
-
-pub(crate) fn parser(input: &str) -> EyreResult<Parsed> {
-    let cols = input.lines().count();
-    let lines = input
-        .find('\n')
-        .ok_or(color_eyre::eyre::eyre!("No lines in input"))?;
-
-    let table = DMatrix::from_iterator(
-        lines,
-        cols,
-        input
-            .as_bytes()
-            .iter()
-            .filter(|&&b| b != b'\n')
-            .map(|b| b - b'0'),
-    );
-
-    Ok(table)
+pub type Parsed = DMatrix<u8>;
+
+pub fn parser(input: &str) -> EyreResult<Parsed> {
+    let (width, digits) = parse::digit_grid(input)?;
+    Ok(DMatrix::from_vec(width, digits.len() / width, digits))
 }
 
 fn flows_into(matrix: &Parsed, i: usize, j: usize) -> Option<(usize, usize)> {
@@ -77,11 +79,11 @@ fn low_points(matrix: &Parsed) -> impl Iterator<Item = ((usize, usize), u8)> + '
         .flatten()
 }
 
-pub(crate) fn part1(matrix: Parsed) -> EyreResult<usize> {
+pub fn part1(matrix: Parsed) -> EyreResult<usize> {
     Ok(low_points(&matrix).map(|(_, p)| 1 + p as usize).sum())
 }
 
-pub(crate) fn part2(matrix: Parsed) -> EyreResult<usize> {
+pub fn part2(matrix: Parsed) -> EyreResult<usize> {
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
     struct Basin {
         parent: (usize, usize),
@@ -144,30 +146,61 @@ pub(crate) fn part2(matrix: Parsed) -> EyreResult<usize> {
         }
     }
 
-    /*
-    for (basin, size) in basins_list.iter() {
-        println!("Basin {:?} (size = {})", basin, size);
-        for j in 0..matrix.ncols() {
-            print!(" ");
-            for i in 0..matrix.nrows() {
-                if find(&mut basins, (i, j)) == *basin {
-                    print!(
-                        "{}",
-                        ansi_term::Colour::Red
-                            .underline()
-                            .bold()
-                            .paint(format!("{}", matrix[(i, j)]))
-                    )
-                } else {
-                    print!("{}", matrix[(i, j)]);
+    if visualize::enabled() || picture::enabled() {
+        // Each basin has the colour of its root
+        let mut sorted_roots: Vec<_> = roots.iter().copied().collect();
+        sorted_roots.sort_unstable();
+        let groups = DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |i, j| {
+            (matrix[(i, j)] != 9).then(|| {
+                let root = find(&mut basins, (i, j));
+                sorted_roots.binary_search(&root).unwrap_or_default()
+            })
+        });
+
+        visualize::show(|| {
+            let mut frame = Frame::new(matrix.nrows(), matrix.ncols());
+            for j in 0..matrix.ncols() {
+                for i in 0..matrix.nrows() {
+                    let glyph = char::from(b'0' + matrix[(i, j)]);
+                    let style = match groups[(i, j)] {
+                        None => Style::new().dimmed(),
+                        Some(index) => visualize::palette(index).bold(),
+                    };
+                    frame.set(i, j, Cell::styled(glyph, style));
                 }
             }
-            println!();
-        }
-    } */
+            frame.caption(format!("{} basins", roots.len()))
+        });
+        picture::draw(|| {
+            let mut picture = Picture::new(matrix.nrows(), matrix.ncols());
+            for j in 0..matrix.ncols() {
+                for i in 0..matrix.nrows() {
+                    if let Some(index) = groups[(i, j)] {
+                        picture.set(i, j, Paint::Group(index));
+                    }
+                }
+            }
+            picture
+        });
+    }
 
     let mut basins: Vec<_> = roots.iter().map(|&k| basins[k].size).collect();
     basins.sort_by(|a, b| b.cmp(a));
 
-    Ok(basins[0] * basins[1] * basins[2])
+    match basins[..] {
+        [a, b, c, ..] => Ok(a * b * c),
+        _ => eyre::bail!("Need at least 3 basins, found {}", basins.len()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parser, part2};
+
+    #[test]
+    fn few_basins() {
+        let err = |input| part2(parser(input).unwrap()).unwrap_err().to_string();
+        assert_eq!(err("1"), "Need at least 3 basins, found 1");
+        assert_eq!(err("1991\n9999"), "Need at least 3 basins, found 2");
+    }
 }
@@ -0,0 +1,306 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use color_eyre::eyre::{self, Context};
+use reqwest::{blocking, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::EyreResult;
+
+pub(crate) const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+const USER_AGENT: &str = concat!(
+    "aoc_2021/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/traxys/aoc_2021)"
+);
+
+/// Client for the Advent of Code website, retrying the requests that failed because of the server
+pub(crate) struct Downloader {
+    base_url: String,
+    session: Option<String>,
+    client: blocking::Client,
+    retries: u32,
+    backoff: Duration,
+}
+
+enum Failure {
+    /// The request may succeed if tried again later
+    Transient(color_eyre::Report),
+    Fatal(color_eyre::Report),
+}
+
+/// Information stored next to a cached input, to detect files that were not fully written
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+    url: String,
+    fetched_at: String,
+    length: u64,
+}
+
+fn metadata_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta.json");
+    path.with_file_name(name)
+}
+
+/// Writes to a temporary file renamed to `path` once complete, so `path` is never partially written
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> EyreResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create the directory {:?}", parent))?;
+    }
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+
+    std::fs::write(&tmp, content).with_context(|| format!("Could not write to file {:?}", tmp))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Could not move {:?} to {:?}", tmp, path))
+}
+
+/// Whether the input at `path` can be used, inputs without metadata are trusted
+pub(crate) fn is_cached(path: &Path) -> bool {
+    let len = match std::fs::metadata(path) {
+        Ok(m) => m.len(),
+        Err(_) => return false,
+    };
+    match std::fs::read_to_string(metadata_path(path)) {
+        Err(_) => true,
+        Ok(meta) => match serde_json::from_str::<Metadata>(&meta) {
+            Ok(meta) => meta.length == len,
+            Err(_) => false,
+        },
+    }
+}
+
+const LOGIN_REQUIRED: &str = "The server asked to log in, the session cookie is missing or expired";
+
+fn asks_to_log_in(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("puzzle inputs differ by user") || body.contains("please log in")
+}
+
+/// Errors out on the pages sent instead of an input, such as the one asking to log in
+fn check_input(body: &str) -> EyreResult<()> {
+    if asks_to_log_in(body) {
+        eyre::bail!(LOGIN_REQUIRED)
+    }
+    let start = body.trim_start().to_lowercase();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        eyre::bail!("Got an HTML page instead of a puzzle input")
+    }
+    Ok(())
+}
+
+impl Downloader {
+    pub(crate) fn new(base_url: &str, session: Option<&str>) -> EyreResult<Self> {
+        let client = blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Could not create the HTTP client")?;
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: session.map(ToString::to_string),
+            client,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        })
+    }
+
+    fn request(&self, method: Method, url: &str) -> blocking::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.session {
+            Some(session) => request.header("Cookie", format!("session={}", session)),
+            None => request,
+        }
+    }
+
+    fn try_send(&self, request: blocking::RequestBuilder) -> Result<String, Failure> {
+        let response = request.send().map_err(|e| {
+            if e.is_connect() || e.is_timeout() {
+                Failure::Transient(e.into())
+            } else {
+                Failure::Fatal(e.into())
+            }
+        })?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .map_err(|e| Failure::Transient(eyre::eyre!(e).wrap_err("Could not read the body")))?;
+        if status.is_success() {
+            Ok(body)
+        } else if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(Failure::Transient(eyre::eyre!(
+                "Server answered {}",
+                status
+            )))
+        } else if asks_to_log_in(&body) {
+            Err(Failure::Fatal(eyre::eyre!(LOGIN_REQUIRED)))
+        } else {
+            Err(Failure::Fatal(eyre::eyre!(
+                "Server answered {}: {}",
+                status,
+                body.trim().lines().next().unwrap_or_default()
+            )))
+        }
+    }
+
+    /// Fetches `path`, relative to the base URL
+    pub(crate) fn get(&self, path: &str) -> EyreResult<String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match self.try_send(self.request(Method::GET, &url)) {
+                Ok(body) => return Ok(body),
+                Err(Failure::Transient(e)) if attempt < self.retries => {
+                    eprintln!("Fetching {} failed ({:#}), retrying in {:?}", url, e, delay);
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(Failure::Transient(e) | Failure::Fatal(e)) => {
+                    return Err(e.wrap_err(format!("Could not fetch {}", url)))
+                }
+            }
+        }
+    }
+
+    /// Posts a form to `path`, relative to the base URL. Failures are not retried as the request
+    /// may have been handled by the server
+    pub(crate) fn post_form(&self, path: &str, form: &[(&str, &str)]) -> EyreResult<String> {
+        let url = format!("{}{}", self.base_url, path);
+        self.try_send(self.request(Method::POST, &url).form(form))
+            .map_err(|(Failure::Transient(e) | Failure::Fatal(e))| {
+                e.wrap_err(format!("Could not post to {}", url))
+            })
+    }
+
+    /// Downloads an input and stores it at `path`
+    pub(crate) fn fetch_input(&self, year: usize, day: usize, path: &Path) -> EyreResult<String> {
+        let body = self
+            .get(&format!("/{}/day/{}/input", year, day))
+            .and_then(|body| check_input(&body).map(|_| body))
+            .with_context(|| format!("Could not download the input for day {} of {}", day, year))?;
+
+        write_atomic(path, body.as_bytes())?;
+        let metadata = Metadata {
+            url: format!("{}/{}/day/{}/input", self.base_url, year, day),
+            fetched_at: chrono::Utc::now().to_rfc3339(),
+            length: body.len() as u64,
+        };
+        write_atomic(&metadata_path(path), &serde_json::to_vec_pretty(&metadata)?)?;
+
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+        time::Duration,
+    };
+
+    use super::{is_cached, metadata_path, Downloader};
+
+    /// Serves each response to a connection in turn, giving back the requests that were received
+    pub(crate) fn serve(responses: Vec<(u16, String)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    request += &line;
+                }
+                let mut content = vec![0; content_length];
+                std::io::Read::read_exact(&mut reader, &mut content).unwrap();
+                request += &String::from_utf8(content).unwrap();
+                requests.push(request);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn downloader(url: &str) -> Downloader {
+        let mut downloader = Downloader::new(url, Some("cookie")).unwrap();
+        downloader.backoff = Duration::from_millis(1);
+        downloader
+    }
+
+    #[test]
+    fn retry_then_store() {
+        let (url, server) = serve(vec![(500, "oops".into()), (200, "1\n2\n".into())]);
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("aoc-download-test-{}", std::process::id()));
+        let path = dir.join("2021").join("day1");
+
+        let body = downloader(&url).fetch_input(2021, 1, &path).unwrap();
+        assert_eq!(body, "1\n2\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n2\n");
+        assert!(is_cached(&path));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /2021/day/1/input "));
+        assert!(requests[1].contains("session=cookie"));
+        assert!(requests[1].to_lowercase().contains("user-agent: aoc_2021/"));
+
+        // A truncated input is not used
+        std::fs::write(&path, "1\n").unwrap();
+        assert!(!is_cached(&path));
+        std::fs::remove_file(metadata_path(&path)).unwrap();
+        assert!(is_cached(&path));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn login_page() {
+        let login = "Puzzle inputs differ by user.  Please log in to get your puzzle input.";
+        let html = "<!DOCTYPE html>\n<html><body>[Log In]</body></html>";
+        let (url, server) = serve(vec![(400, login.into()), (200, html.into())]);
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("aoc-download-login-test-{}", std::process::id()));
+        let path = dir.join("day1");
+
+        let downloader = downloader(&url);
+        let err = downloader.fetch_input(2021, 1, &path).unwrap_err();
+        assert!(format!("{:#}", err).contains("log in"));
+        let err = downloader.fetch_input(2021, 1, &path).unwrap_err();
+        assert!(format!("{:#}", err).contains("HTML"));
+        assert!(!path.exists());
+
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+//! Example inputs of the puzzles, stored as `day<N>` or `day<N>-<K>` with their expected answers
+//! in a `.expected` file next to them. The examples of another calendar than the default one are
+//! in a `<year>` directory
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{self, Context};
+
+use crate::{solution::DEFAULT_YEAR, EyreResult, Part};
+
+/// Expected answers of an example, written as `part1: <answer>` lines. Lines starting with `#` are
+/// comments, and a part without a line is not checked
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Expected {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+impl Expected {
+    pub fn get(&self, part: Part) -> Option<&str> {
+        match part {
+            Part::Part1 => self.part1.as_deref(),
+            Part::Part2 => self.part2.as_deref(),
+        }
+    }
+
+    pub fn parse(content: &str) -> EyreResult<Self> {
+        let mut expected = Self::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| eyre::eyre!("Expected `part<N>: <answer>`, got {:?}", line))?;
+            let value = Some(value.trim().to_string());
+            match key.trim() {
+                "part1" => expected.part1 = value,
+                "part2" => expected.part2 = value,
+                other => eyre::bail!("Unknown part: {:?}", other),
+            }
+        }
+        Ok(expected)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in [("part1", &self.part1), ("part2", &self.part2)] {
+            if let Some(value) = value {
+                out += &format!("{}: {}\n", key, value);
+            }
+        }
+        out
+    }
+}
+
+pub struct Example {
+    /// File name of the example, such as `day16-2`
+    pub name: String,
+    pub day: usize,
+    pub path: PathBuf,
+}
+
+/// Directory of the examples of `year` in the examples directory `dir`
+pub fn year_dir(dir: &Path, year: usize) -> PathBuf {
+    if year == DEFAULT_YEAR {
+        dir.to_path_buf()
+    } else {
+        dir.join(year.to_string())
+    }
+}
+
+pub fn sidecar_path(example: &Path) -> PathBuf {
+    example.with_extension("expected")
+}
+
+impl Example {
+    /// The expected answers, `None` if the example has no sidecar
+    pub fn expected(&self) -> EyreResult<Option<Expected>> {
+        let path = sidecar_path(&self.path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read the expected answers at {:?}", path))?;
+        Expected::parse(&content)
+            .map(Some)
+            .with_context(|| format!("Malformed expected answers at {:?}", path))
+    }
+}
+
+/// Day of an example from its file name, files with an extension are not examples
+fn example_day(name: &str) -> Option<usize> {
+    if name.contains('.') {
+        return None;
+    }
+    let day = name.strip_prefix("day")?;
+    let day = day.split_once('-').map(|(day, _)| day).unwrap_or(day);
+    day.parse().ok()
+}
+
+/// Finds all the examples in `dir`, ordered by day and name
+pub fn discover(dir: &Path) -> EyreResult<Vec<Example>> {
+    let mut examples = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Could not read the directory {:?}", dir))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(day) = example_day(&name) {
+            examples.push(Example {
+                name,
+                day,
+                path: entry.path(),
+            });
+        }
+    }
+    examples.sort_by(|a, b| (a.day, &a.name).cmp(&(b.day, &b.name)));
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{example_day, Expected};
+
+    #[test]
+    fn sidecar() {
+        let expected = Expected::parse("# from the puzzle\npart1: 17\n\npart2 :  a b \n").unwrap();
+        assert_eq!(expected.part1.as_deref(), Some("17"));
+        assert_eq!(expected.part2.as_deref(), Some("a b"));
+        assert_eq!(Expected::parse(&expected.render()).unwrap(), expected);
+        assert!(Expected::parse("part3: 1").is_err());
+
+        assert_eq!(example_day("day16-2"), Some(16));
+        assert_eq!(example_day("day5"), Some(5));
+        assert_eq!(example_day("day12-1.dot"), None);
+        assert_eq!(example_day("day5.expected"), None);
+    }
+}
@@ -1,29 +1,50 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
 
-pub(crate) struct RunParams {
-    pub(crate) timings: bool,
+use color_eyre::eyre;
+
+use crate::{cancel, memory::AllocStats, picture::Picture, EyreResult};
+
+pub struct RunParams {
+    pub timings: bool,
 }
 
-pub(crate) struct Timings {
-    parse: Duration,
-    inter: Option<Duration>,
-    part: Duration,
+#[derive(Clone, Copy)]
+pub struct Timings {
+    /// `None` when the parsed input was reused from a previous part
+    pub parse: Option<Duration>,
+    pub inter: Option<Duration>,
+    pub part: Duration,
 }
 
-pub(crate) struct ExtraInfo {
+/// Heap use of each step of a run, mirroring [`Timings`]
+#[derive(Clone, Copy)]
+pub struct Allocations {
+    pub parse: Option<AllocStats>,
+    pub inter: Option<AllocStats>,
+    pub part: AllocStats,
+}
+
+struct ExtraInfo {
     timings: Option<Timings>,
+    allocations: Option<Allocations>,
 }
 
 impl ExtraInfo {
     fn render(&self) -> Option<String> {
-        self.timings.as_ref().map(|timings| {
-            let mut out = format!(
-                "  Timings:\n    - parsing: {}\n",
-                humantime::format_duration(timings.parse)
-            );
+        let timings = self.timings.as_ref().map(|timings| {
+            let mut out = String::from("  Timings:\n");
+            if let Some(parse) = timings.parse {
+                out += &format!("    - parsing: {}\n", humantime::format_duration(parse));
+            }
             if let Some(inter) = timings.inter {
                 out += &format!(
-                    "    - intermediary (part1): {}\n",
+                    "    - intermediary (shared): {}\n",
                     humantime::format_duration(inter)
                 )
             }
@@ -32,35 +53,98 @@ impl ExtraInfo {
                 humantime::format_duration(timings.part)
             );
             out
-        })
+        });
+        let allocations = self.allocations.as_ref().map(|allocations| {
+            let mut out = String::from("  Allocations:\n");
+            if let Some(parse) = allocations.parse {
+                out += &format!("    - parsing: {}\n", parse);
+            }
+            if let Some(inter) = allocations.inter {
+                out += &format!("    - intermediary (shared): {}\n", inter);
+            }
+            out += &format!("    - solution: {}", allocations.part);
+            out
+        });
+
+        match (timings, allocations) {
+            (Some(timings), Some(allocations)) => Some(timings + "\n" + &allocations),
+            (timings, allocations) => timings.or(allocations),
+        }
     }
 }
 
-pub(crate) fn render(
-    solution: String,
-    parsed_time: Duration,
-    part_time: Duration,
-    timings: bool,
-) -> String {
+/// Result of running a single part of a day
+pub struct Outcome {
+    /// The bare answer, as it would be submitted
+    pub answer: String,
+    /// The answer formatted with the message of the day
+    pub message: String,
+    pub timings: Timings,
+    /// `None` unless the allocations are counted
+    pub allocations: Option<Allocations>,
+    /// `None` unless the pictures are drawn and the part draws one
+    pub picture: Option<Picture>,
+}
+
+/// Renders the message of the outcome, followed by its timings and heap use when `timings` is set
+pub fn render(outcome: &Outcome, timings: bool) -> String {
     let info = ExtraInfo {
-        timings: if timings {
-            Some(Timings {
-                parse: parsed_time,
-                part: part_time,
-                inter: None,
-            })
-        } else {
-            None
-        },
+        timings: if timings { Some(outcome.timings) } else { None },
+        allocations: if timings { outcome.allocations } else { None },
     };
 
     match info.render() {
-        None => solution,
-        Some(info) => solution + "\n" + &info,
+        None => outcome.message.clone(),
+        Some(info) => outcome.message.clone() + "\n" + &info,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Renders rows of cells as columns separated by `|`, each column as wide as its largest cell
+pub fn render_table(header: &[(&str, Align)], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<_> = header
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = std::cmp::max(*width, cell.chars().count());
+        }
+    }
+
+    let render_line = |line: &mut dyn Iterator<Item = &str>| {
+        line.zip(header)
+            .zip(&widths)
+            .map(|((cell, (_, align)), &width)| match align {
+                Align::Left => format!("{:<width$}", cell, width = width),
+                Align::Right => format!("{:>width$}", cell, width = width),
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = render_line(&mut header.iter().map(|(name, _)| *name));
+    out.push('\n');
+    out += &widths
+        .iter()
+        .map(|&w| "-".repeat(w))
+        .collect::<Vec<_>>()
+        .join("-+-");
+    for row in rows {
+        out.push('\n');
+        out += &render_line(&mut row.iter().map(String::as_str));
     }
+    out
 }
 
-pub(crate) fn time_func<F, I, O>(mut f: F, a: I) -> (O, Duration)
+pub fn time_func<F, I, O>(mut f: F, a: I) -> (O, Duration)
 where
     F: FnMut(I) -> O,
 {
@@ -69,8 +153,92 @@ where
     (res, start.elapsed())
 }
 
-#[derive(Clone, Copy)]
-pub(crate) enum Part {
+/// Runs `f` on a worker thread, giving up after `timeout`. The run is then cancelled, so that the
+/// loops polling [`cancel::check`] stop. A solution that doesn't poll it can't be stopped, and keeps
+/// running on its detached thread after the timeout until it is done
+pub fn with_timeout<O, F>(timeout: Option<Duration>, f: F) -> EyreResult<O>
+where
+    O: Send + 'static,
+    F: FnOnce() -> EyreResult<O> + Send + 'static,
+{
+    let timeout = match timeout {
+        None => return f(),
+        Some(timeout) => timeout,
+    };
+
+    let token = cancel::Token::new();
+    let (send, receive) = mpsc::channel();
+    let worker_token = token.clone();
+    std::thread::Builder::new()
+        .name("solver".into())
+        // Same as the main thread, some solvers recurse deeply
+        .stack_size(8 << 20)
+        .spawn(move || {
+            // The receiver is gone when the run timed out
+            let _ = send.send(cancel::scope(worker_token, f));
+        })?;
+
+    match receive.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            token.cancel();
+            eyre::bail!("Timed out after {}", humantime::format_duration(timeout))
+        }
+        Err(RecvTimeoutError::Disconnected) => eyre::bail!("The solver panicked"),
+    }
+}
+
+/// Runs `f` on each item using `jobs` threads, handing the results to `done` in the order of the
+/// items as soon as all the previous ones are done. No new item is started once `done` fails.
+pub fn run_ordered<I, O, F, D>(jobs: usize, items: &[I], f: F, mut done: D) -> EyreResult<()>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync,
+    D: FnMut(O) -> EyreResult<()>,
+{
+    if jobs <= 1 {
+        return items.iter().try_for_each(|item| done(f(item)));
+    }
+
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let (send, receive) = mpsc::channel();
+        for _ in 0..jobs.min(items.len()) {
+            let (send, next, f) = (send.clone(), &next, &f);
+            std::thread::Builder::new()
+                .name("job".into())
+                // Same as the main thread, some solvers recurse deeply
+                .stack_size(8 << 20)
+                .spawn_scoped(scope, move || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    match items.get(index) {
+                        // The receiver is gone when `done` failed
+                        Some(item) if send.send((index, f(item))).is_ok() => (),
+                        _ => break,
+                    }
+                })?;
+        }
+        drop(send);
+
+        let mut pending: Vec<Option<O>> = items.iter().map(|_| None).collect();
+        let mut first = 0;
+        for (index, out) in receive {
+            pending[index] = Some(out);
+            while let Some(out) = pending.get_mut(first).and_then(Option::take) {
+                first += 1;
+                if let Err(e) = done(out) {
+                    next.store(items.len(), Ordering::Relaxed);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Part {
     Part1,
     Part2,
 }
@@ -85,158 +253,81 @@ impl std::fmt::Display for Part {
     }
 }
 
-#[macro_export]
-macro_rules! day {
-    (
-        $parser:path,
-        $part1:path => $fmt1:literal $(,)?
-    ) => {
-        pub(crate) fn solver_latest() -> crate::Part {
-            crate::Part::Part1
-        }
-
-        pub(crate) fn solve<'i>(
-            part: crate::Part,
-            input: &'i str,
-            params: crate::harness::RunParams,
-        ) -> crate::EyreResult<String> {
-            let (parsed, parsed_time) = crate::harness::time_func($parser, input);
-            let (result, part_time) = match part {
-                crate::Part::Part1 => {
-                    let (part, part_time) = crate::harness::time_func($part1, parsed?);
-                    (format!($fmt1, part?), part_time)
-                }
-                crate::Part::Part2 => color_eyre::eyre::bail!("part 2 is not implemented"),
-            };
-
-            Ok(crate::harness::render(
-                result,
-                parsed_time,
-                part_time,
-                params.timings,
-            ))
-        }
-    };
-    (
-        $parser:path,
-        $part1:path => $fmt1:literal,
-        $part2:path => $fmt2:literal $(,)?
-    ) => {
-        pub(crate) fn solver_latest() -> crate::Part {
-            crate::Part::Part2
-        }
+impl std::str::FromStr for Part {
+    type Err = color_eyre::Report;
 
-        pub(crate) fn solve<'i>(
-            part: crate::Part,
-            input: &'i str,
-            params: crate::harness::RunParams,
-        ) -> crate::EyreResult<String> {
-            let (parsed, parsed_time) = crate::harness::time_func($parser, input);
-            let (result, part_time) = match part {
-                crate::Part::Part1 => {
-                    let (part, part_time) = crate::harness::time_func($part1, parsed?);
-                    (format!($fmt1, part?), part_time)
-                }
-                crate::Part::Part2 => {
-                    let (part, part_time) = crate::harness::time_func($part2, parsed?);
-                    (format!($fmt2, part?), part_time)
-                }
-            };
-
-            Ok(crate::harness::render(
-                result,
-                parsed_time,
-                part_time,
-                params.timings,
-            ))
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "1" => Ok(Part::Part1),
+            "2" => Ok(Part::Part2),
+            _ => color_eyre::eyre::bail!("No such part: {}", s),
         }
-    };
+    }
 }
 
-#[macro_export]
-macro_rules! days {
-    ($($day:literal = $mod:ident,)*) => {
-        const IMPLEMENTED_DAYS: &[usize] = &[$($day,)*];
-        const POSSIBLE_DAYS: &[&str] = &["latest", $(stringify!($day),)*];
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, time::Duration};
 
-        $(
-            pub(crate) mod $mod;
-        )*
+    use super::{run_ordered, with_timeout};
+    use crate::cancel;
 
-        fn resolve_day(day: Day) -> usize {
-            match day {
-                Day::Latest => *IMPLEMENTED_DAYS.last().unwrap(),
-                Day::Specific(x) => x,
-            }
+    #[test]
+    fn ordered() {
+        let items: Vec<u64> = (0..20).rev().collect();
+        for jobs in [1, 4] {
+            let mut results = Vec::new();
+            run_ordered(
+                jobs,
+                &items,
+                |&item| {
+                    // The first items finish last
+                    std::thread::sleep(Duration::from_millis(item));
+                    item * 2
+                },
+                |out| {
+                    results.push(out);
+                    Ok(())
+                },
+            )
+            .unwrap();
+            assert_eq!(results, items.iter().map(|i| i * 2).collect::<Vec<_>>());
         }
 
-        fn run_solution(
-            day: usize,
-            part: Option<crate::Part>,
-            input: &str,
-            params: crate::harness::RunParams
-        ) -> EyreResult<()> {
-            match day {
-                $(
-                    $day => {
-                        let part = part.unwrap_or($mod::solver_latest());
-                        let result = $mod::solve(part, input, params)?;
-                        println!("Day {} Part {}:\n  {}", day, part, result);
-                        Ok(())
-                    }
-                )*
-                _ => eyre::bail!("Day {} was not implemented", day),
-            }
-        }
-    };
-}
+        let mut seen = 0;
+        let err = run_ordered(
+            4,
+            &items,
+            |&item| item,
+            |_| {
+                seen += 1;
+                color_eyre::eyre::ensure!(seen < 3, "stop");
+                Ok(())
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "stop");
+        assert_eq!(seen, 3);
+    }
 
-#[macro_export]
-macro_rules! solutions {
-     ($(
-         day $day:literal {
-             module: $mod:path,
-             linking: $link:ty $(,)?
-         }
-     ),* $(,)?) => {
-         const IMPLEMENTED_DAYS: &[usize] = &[$($day,)*];
-         const POSSIBLE_DAYS: &[&str] = &["latest", $(stringify!($day),)*];
-
-         fn resolve_day(day: Day) -> usize {
-             match day {
-                 Day::Latest => *IMPLEMENTED_DAYS.last().unwrap(),
-                 Day::Specific(x) => x,
-             }
-         }
-
-         fn run_solution(day: usize, part: usize, input: &str, params: crate::harness::RunParams) -> EyreResult<()> {
-             match day {
-                 $(
-                     $day => {
-                         use $mod as module;
-
-                         let mut problem: crate::harness::Problem<_, _, _, _, _, $link, _, _, _> = crate::harness::Problem {
-                             parser: module::parser,
-                             part1: module::part1,
-                             part2: module::part2,
-                             fmt1: module::fmt1,
-                             fmt2: module::fmt2,
-                             _cont: Default::default(),
-                             _inp: Default::default(),
-                             _outp1: Default::default(),
-                             _outp2: Default::default(),
-                         };
-                         let result = match part {
-                             1 => problem.part1(input, params)?,
-                             2 => problem.part2(input, params)?,
-                             _ => eyre::bail!("Part {} is not possible", part),
-                         };
-                         println!("Day {} Part {}:\n  {}", day, part, result);
-                     },
-                 )*
-                 _ => eyre::bail!("Day {} was not implemented", day),
-             };
-             Ok(())
-         }
-     };
+    #[test]
+    fn timeout() {
+        assert_eq!(
+            with_timeout(Some(Duration::from_secs(10)), || Ok(42)).unwrap(),
+            42
+        );
+
+        let (send, stopped) = mpsc::channel();
+        let err = with_timeout(Some(Duration::from_millis(20)), move || loop {
+            if let Err(e) = cancel::check() {
+                send.send(()).unwrap();
+                return Err::<(), _>(e.into());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Timed out after 20ms");
+        // The solver noticed the cancellation
+        stopped.recv_timeout(Duration::from_secs(10)).unwrap();
+    }
 }
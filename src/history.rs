@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, io::Write, path::Path, process::Command, time::Duration};
+
+use color_eyre::eyre::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bench::Stats,
+    harness::{self, Align},
+    EyreResult,
+};
+
+/// Timings of a run as stored in the history file, one JSON object per line. Durations are in
+/// nanoseconds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Run {
+    pub(crate) ran_at: String,
+    /// Commit of the checkout, marked `+dirty` when it has changes
+    pub(crate) commit: Option<String>,
+    pub(crate) machine: String,
+    /// Hash of the input, runs are only compared on the same input
+    pub(crate) input: String,
+    pub(crate) year: usize,
+    pub(crate) day: usize,
+    pub(crate) part: String,
+    pub(crate) parse_ns: Option<u128>,
+    pub(crate) inter_ns: Option<u128>,
+    pub(crate) solve_ns: u128,
+}
+
+/// FNV-1a hash of an input, stable across builds unlike the hashers of std
+pub(crate) fn hash_input(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The commit checked out in the current directory, if it is a git repository
+pub(crate) fn current_commit() -> Option<String> {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let commit = git(&["rev-parse", "--short", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+    Some(if dirty { commit + "+dirty" } else { commit })
+}
+
+/// Name of the machine: `$AOC_MACHINE`, or else the host name
+pub(crate) fn machine() -> String {
+    std::env::var("AOC_MACHINE")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+pub(crate) fn append(path: &Path, runs: &[Run]) -> EyreResult<()> {
+    let mut out = String::new();
+    for run in runs {
+        out += &serde_json::to_string(run)?;
+        out.push('\n');
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(out.as_bytes()))
+        .with_context(|| format!("Could not add the runs to the history at {:?}", path))
+}
+
+/// The runs of the history, from the oldest, which is empty when there is no history yet
+pub(crate) fn load(path: &Path) -> EyreResult<Vec<Run>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Could not read {:?}", path)),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Malformed run on line {} of {:?}", idx + 1, path))
+        })
+        .collect()
+}
+
+/// How to compare each run with the ones before it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    /// Number of previous runs the median is taken over
+    pub(crate) window: usize,
+    /// Ratio to the median above which a run is slower
+    pub(crate) factor: f64,
+    /// Number of runs shown for each day and part
+    pub(crate) last: usize,
+}
+
+/// Ratio of each timing to the median of the `window` ones before it, `None` for the first one
+fn ratios(timings: &[Option<u128>], window: usize) -> Vec<Option<f64>> {
+    let mut previous: Vec<Duration> = Vec::new();
+    timings
+        .iter()
+        .map(|&timing| {
+            let timing = Duration::from_nanos(timing? as u64);
+            let ratio = (!previous.is_empty()).then(|| {
+                let mut samples = previous[previous.len().saturating_sub(window)..].to_vec();
+                let median = Stats::from_samples(&mut samples).median;
+                timing.as_secs_f64() / median.as_secs_f64().max(1e-9)
+            });
+            previous.push(timing);
+            ratio
+        })
+        .collect()
+}
+
+/// Renders the runs of each day and part on a machine and input, returning the number of them
+/// whose latest run is slower than the median
+pub(crate) fn render(runs: &[&Run], settings: Settings) -> (String, usize) {
+    let mut series: BTreeMap<_, Vec<&Run>> = BTreeMap::new();
+    for run in runs {
+        let key = (run.year, run.day, &run.part, &run.machine, &run.input);
+        series.entry(key).or_default().push(run);
+    }
+
+    let mut regressions = 0;
+    let mut out = Vec::new();
+    for ((year, day, part, machine, input), runs) in series {
+        let parse: Vec<_> = runs.iter().map(|run| run.parse_ns).collect();
+        let solve: Vec<_> = runs.iter().map(|run| Some(run.solve_ns)).collect();
+        let (parse, solve) = (
+            ratios(&parse, settings.window),
+            ratios(&solve, settings.window),
+        );
+        let slower = |ratio: Option<f64>| ratio.is_some_and(|ratio| ratio > settings.factor);
+        let compared = |ratio: Option<f64>| match ratio {
+            None => "-".to_string(),
+            Some(ratio) if slower(Some(ratio)) => format!("x{:.2} SLOWER", ratio),
+            Some(ratio) => format!("x{:.2}", ratio),
+        };
+        let last = runs.len() - 1;
+        if slower(parse[last]) || slower(solve[last]) {
+            regressions += 1;
+        }
+
+        let duration = |ns: u128| format!("{:.2?}", Duration::from_nanos(ns as u64));
+        let rows: Vec<_> = (0..runs.len())
+            .skip(runs.len().saturating_sub(settings.last))
+            .map(|idx| {
+                let run = runs[idx];
+                vec![
+                    run.ran_at.clone(),
+                    run.commit.clone().unwrap_or_else(|| "-".into()),
+                    run.parse_ns.map_or_else(|| "-".into(), duration),
+                    compared(parse[idx]),
+                    duration(run.solve_ns),
+                    compared(solve[idx]),
+                ]
+            })
+            .collect();
+        out.push(format!(
+            "{} day {} part {} on {} with input {}, {} run(s)\n{}",
+            year,
+            day,
+            part,
+            machine,
+            input,
+            runs.len(),
+            harness::render_table(
+                &[
+                    ("Ran at", Align::Left),
+                    ("Commit", Align::Left),
+                    ("Parse", Align::Right),
+                    ("vs median", Align::Left),
+                    ("Solve", Align::Right),
+                    ("vs median", Align::Left),
+                ],
+                &rows,
+            )
+        ));
+    }
+    (out.join("\n"), regressions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash_input, ratios, render, Run, Settings};
+
+    #[test]
+    fn regressions() {
+        assert_eq!(hash_input(""), "cbf29ce484222325");
+        assert_eq!(hash_input("a"), "af63dc4c8601ec8c");
+
+        let ratios: Vec<_> = ratios(&[Some(10), Some(30), None, Some(20), Some(60)], 2)
+            .into_iter()
+            .map(|ratio| ratio.map(|ratio| (ratio * 100.).round()))
+            .collect();
+        assert_eq!(ratios, [None, Some(300.), None, Some(100.), Some(240.)]);
+
+        let run = |solve_ns, input: &str| Run {
+            ran_at: "2021-12-25T06:00:00+00:00".into(),
+            commit: Some("abc1234".into()),
+            machine: "desk".into(),
+            input: input.into(),
+            year: 2021,
+            day: 1,
+            part: "1".into(),
+            parse_ns: None,
+            inter_ns: None,
+            solve_ns,
+        };
+        let line = serde_json::to_string(&run(1, "a")).unwrap();
+        assert_eq!(serde_json::from_str::<Run>(&line).unwrap(), run(1, "a"));
+
+        let runs = [run(100, "a"), run(110, "a"), run(300, "a"), run(1000, "b")];
+        let settings = Settings {
+            window: 5,
+            factor: 1.5,
+            last: 2,
+        };
+        let (out, regressions) = render(&runs.iter().collect::<Vec<_>>(), settings);
+        assert_eq!(regressions, 1);
+        assert!(out.contains("on desk with input a, 3 run(s)"));
+        assert!(out.contains("x2.86 SLOWER"));
+        assert!(!out.contains("100.00ns"));
+        let (_, regressions) = render(&runs[..2].iter().collect::<Vec<_>>(), settings);
+        assert_eq!(regressions, 0);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::eyre::{self, Context};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    download::{self, Downloader},
+    harness::{self, Align},
+    EyreResult,
+};
+
+/// The site asks not to fetch a leaderboard more often than this
+pub(crate) const REFRESH: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Leaderboard {
+    event: String,
+    members: BTreeMap<String, Member>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    id: u64,
+    name: Option<String>,
+    stars: u32,
+    /// Score computed by the site
+    local_score: u64,
+    #[serde(default)]
+    completion_day_level: BTreeMap<String, BTreeMap<String, Star>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Star {
+    #[serde(deserialize_with = "timestamp")]
+    get_star_ts: i64,
+    /// Order in which the stars were obtained, only given in recent leaderboards
+    #[serde(default)]
+    star_index: u64,
+}
+
+/// Timestamps are numbers, but older leaderboards give them as strings
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Number(i64),
+        Text(String),
+    }
+
+    match Timestamp::deserialize(deserializer)? {
+        Timestamp::Number(ts) => Ok(ts),
+        Timestamp::Text(ts) => ts.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+impl Member {
+    fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+
+    /// When the star of `part` was obtained on `day`
+    fn star(&self, day: usize, part: usize) -> Option<&Star> {
+        self.completion_day_level
+            .get(&day.to_string())?
+            .get(&part.to_string())
+    }
+}
+
+pub(crate) fn parse(json: &str) -> EyreResult<Leaderboard> {
+    serde_json::from_str(json).context("Malformed leaderboard")
+}
+
+/// Checks that the id of a leaderboard is a number, as it is part of a URL and a path
+pub(crate) fn check_id(id: &str) -> EyreResult<()> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        eyre::bail!("Leaderboard ids are numbers, got {:?}", id);
+    }
+    Ok(())
+}
+
+/// Fetches the leaderboard, or reads it from `cache` if it was fetched less than [`REFRESH`] ago
+pub(crate) fn fetch(
+    client: &Downloader,
+    year: usize,
+    id: &str,
+    cache: &Path,
+) -> EyreResult<String> {
+    let age = std::fs::metadata(cache)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if let Some(age) = age.filter(|&age| age < REFRESH) {
+        eprintln!(
+            "Using the leaderboard fetched {} ago, it is fetched again after {}",
+            humantime::format_duration(Duration::from_secs(age.as_secs())),
+            humantime::format_duration(REFRESH)
+        );
+        return Ok(std::fs::read_to_string(cache)?);
+    }
+
+    let json = client
+        .get(&format!("/{}/leaderboard/private/view/{}.json", year, id))
+        .and_then(|json| parse(&json).map(|_| json))
+        .with_context(|| format!("Could not fetch the leaderboard {} of {}", id, year));
+    match json {
+        Ok(json) => {
+            download::write_atomic(cache, json.as_bytes())?;
+            Ok(json)
+        }
+        Err(e) if age.is_some() => {
+            eprintln!("{:#}, using the one fetched before", e);
+            Ok(std::fs::read_to_string(cache)?)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The local score of each member: for each star, the first of the `n` members to get it gains
+/// `n` points, the next one `n - 1`, and so on
+fn local_scores(leaderboard: &Leaderboard) -> HashMap<u64, u64> {
+    let members = &leaderboard.members;
+    let mut scores: HashMap<_, _> = members.values().map(|member| (member.id, 0)).collect();
+    for day in 1..=25 {
+        for part in 1..=2 {
+            let mut stars: Vec<_> = members
+                .values()
+                .filter_map(|member| {
+                    let star = member.star(day, part)?;
+                    Some(((star.get_star_ts, star.star_index), member.id))
+                })
+                .collect();
+            stars.sort_unstable();
+            for (rank, (_, id)) in stars.into_iter().enumerate() {
+                *scores.get_mut(&id).unwrap() += (members.len() - rank) as u64;
+            }
+        }
+    }
+    scores
+}
+
+/// Renders the ranking of the members with their stars, and the time each took to go from the
+/// first to the second part of each day
+pub(crate) fn render(leaderboard: &Leaderboard, id: &str) -> String {
+    let scores = local_scores(leaderboard);
+    let mut members: Vec<_> = leaderboard.members.values().collect();
+    members.sort_by_key(|member| {
+        let last = (1..=25)
+            .flat_map(|day| (1..=2).filter_map(move |part| member.star(day, part)))
+            .map(|star| star.get_star_ts)
+            .max();
+        (
+            std::cmp::Reverse(scores[&member.id]),
+            std::cmp::Reverse(member.stars),
+            last,
+            member.id,
+        )
+    });
+
+    let days = (1..=25)
+        .filter(|&day| members.iter().any(|member| member.star(day, 1).is_some()))
+        .max()
+        .unwrap_or(0);
+    let ranking: Vec<_> = members
+        .iter()
+        .enumerate()
+        .map(|(rank, member)| {
+            let stars: String = (1..=days)
+                .map(|day| match (member.star(day, 1), member.star(day, 2)) {
+                    (Some(_), Some(_)) => '*',
+                    (Some(_), None) => '+',
+                    _ => '.',
+                })
+                .collect();
+            vec![
+                (rank + 1).to_string(),
+                member.display_name(),
+                scores[&member.id].to_string(),
+                member.stars.to_string(),
+                stars,
+            ]
+        })
+        .collect();
+
+    let mut out = format!(
+        "Private leaderboard {} of {}, {} member(s)\n{}",
+        id,
+        leaderboard.event,
+        members.len(),
+        harness::render_table(
+            &[
+                ("Rank", Align::Right),
+                ("Member", Align::Left),
+                ("Score", Align::Right),
+                ("Stars", Align::Right),
+                ("Days (* both parts, + first part)", Align::Left),
+            ],
+            &ranking,
+        )
+    );
+    let differ = members
+        .iter()
+        .filter(|member| member.local_score != scores[&member.id])
+        .count();
+    if differ != 0 {
+        out += &format!(
+            "\n\nThe site gives a different score to {} member(s), it may not count some stars",
+            differ
+        );
+    }
+
+    let delta = |member: &Member, day| {
+        let (first, second) = (member.star(day, 1)?, member.star(day, 2)?);
+        let secs = (second.get_star_ts - first.get_star_ts).max(0) as u64;
+        Some(humantime::format_duration(Duration::from_secs(secs)).to_string())
+    };
+    let solved: Vec<_> = (1..=days)
+        .filter(|&day| members.iter().any(|member| delta(member, day).is_some()))
+        .collect();
+    if !solved.is_empty() {
+        let headers: Vec<_> = solved.iter().map(|day| format!("Day {}", day)).collect();
+        let mut header = vec![("Member", Align::Left)];
+        header.extend(headers.iter().map(|day| (day.as_str(), Align::Right)));
+        let rows: Vec<_> = members
+            .iter()
+            .map(|member| {
+                let mut row = vec![member.display_name()];
+                row.extend(
+                    solved
+                        .iter()
+                        .map(|&day| delta(member, day).unwrap_or_else(|| "-".into())),
+                );
+                row
+            })
+            .collect();
+        out += &format!(
+            "\n\nTime from the first to the second part\n{}",
+            harness::render_table(&header, &rows)
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{check_id, fetch, local_scores, parse, render};
+    use crate::download::{test::serve, Downloader};
+
+    const LEADERBOARD: &str = r#"{
+        "owner_id": 1,
+        "event": "2021",
+        "members": {
+            "1": {
+                "id": 1, "name": "alice", "stars": 3, "local_score": 8, "global_score": 0,
+                "last_star_ts": 1638421200,
+                "completion_day_level": {
+                    "1": {"1": {"get_star_ts": 1638334800, "star_index": 1},
+                          "2": {"get_star_ts": 1638335100, "star_index": 3}},
+                    "2": {"1": {"get_star_ts": 1638421200, "star_index": 7}}
+                }
+            },
+            "2": {
+                "id": 2, "name": null, "stars": 2, "local_score": 3, "global_score": 0,
+                "last_star_ts": "1638334900",
+                "completion_day_level": {
+                    "1": {"1": {"get_star_ts": "1638334860"},
+                          "2": {"get_star_ts": "1638334900"}}
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn standings() {
+        let leaderboard = parse(LEADERBOARD).unwrap();
+        let scores = local_scores(&leaderboard);
+        // With 2 members the first to get a star gains 2 points and the second one 1
+        assert_eq!((scores[&1], scores[&2]), (5, 3));
+
+        let out = render(&leaderboard, "1");
+        assert!(out.starts_with("Private leaderboard 1 of 2021, 2 member(s)\n"));
+        assert!(out.contains("   1 | alice               |     5 |     3 | *+"));
+        assert!(out.contains("   2 | (anonymous user #2) |     3 |     2 | *."));
+        assert!(out.contains("different score to 1 member(s)"));
+        assert!(out.contains("alice               |    5m"));
+        assert!(out.contains("(anonymous user #2) |   40s"));
+
+        assert!(check_id("123").is_ok());
+        assert!(check_id("../123").is_err());
+    }
+
+    #[test]
+    fn cache() {
+        let (url, server) = serve(vec![(200, LEADERBOARD.into())]);
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("aoc-leaderboard-test-{}", std::process::id()));
+        let cache = dir.join("leaderboard-1.json");
+
+        let client = Downloader::new(&url, Some("cookie")).unwrap();
+        assert_eq!(fetch(&client, 2021, "1", &cache).unwrap(), LEADERBOARD);
+        // The second call is under the rate limit, the server would not answer it
+        assert_eq!(fetch(&client, 2021, "1", &cache).unwrap(), LEADERBOARD);
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /2021/leaderboard/private/view/1.json "));
+
+        let stale = std::fs::File::options().write(true).open(&cache).unwrap();
+        stale
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+        let (url, server) = serve(vec![(404, "Not found".into())]);
+        let client = Downloader::new(&url, Some("cookie")).unwrap();
+        assert_eq!(fetch(&client, 2021, "1", &cache).unwrap(), LEADERBOARD);
+        server.join().unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
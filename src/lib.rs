@@ -0,0 +1,74 @@
+//! Solutions to the Advent of Code puzzles
+//!
+//! Each day module exposes its `parser`, `part1` and `part2` functions along with the types they
+//! work on, and a type implementing [`solution::Solution`] used by the [`registry`].
+
+pub mod cancel;
+pub mod examples;
+pub mod harness;
+pub mod memory;
+pub mod params;
+pub mod parse;
+pub mod picture;
+pub mod solution;
+pub mod trace;
+pub mod utils;
+pub mod visualize;
+
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+
+pub use harness::Part;
+use solution::{Entry, Registry};
+
+pub type EyreResult<T, E = color_eyre::Report> = Result<T, E>;
+
+/// All the solutions of this crate
+pub fn registry() -> Registry {
+    Registry::new(vec![
+        Entry::of::<day1::Day1>(),
+        Entry::of::<day2::Day2>(),
+        Entry::of::<day3::Day3>(),
+        Entry::of::<day4::Day4>(),
+        Entry::of::<day5::Day5>(),
+        Entry::of::<day6::Day6>(),
+        Entry::of::<day7::Day7>(),
+        Entry::of::<day8::Day8>(),
+        Entry::of::<day9::Day9>(),
+        Entry::of::<day10::Day10>(),
+        Entry::of::<day11::Day11>(),
+        Entry::of::<day12::Day12>(),
+        Entry::of::<day13::Day13>(),
+        Entry::of::<day14::Day14>(),
+        Entry::of::<day15::Day15>(),
+        Entry::of::<day16::Day16>(),
+        Entry::of::<day17::Day17>(),
+        Entry::of::<day18::Day18>(),
+        Entry::of::<day19::Day19>(),
+        Entry::of::<day20::Day20>(),
+        Entry::of::<day21::Day21>(),
+        Entry::of::<day22::Day22>(),
+        Entry::of::<day23::Day23>(),
+    ])
+}
@@ -1,47 +1,72 @@
 use chrono::Datelike;
 use color_eyre::eyre::{self, Context};
-use std::{fs::OpenOptions, io::Write, path::PathBuf, str::FromStr};
-use structopt::StructOpt;
-
-mod harness;
-
-pub(crate) use harness::Part;
-use harness::RunParams;
-
-pub type EyreResult<T, E = color_eyre::Report> = Result<T, E>;
-
-pub(crate) mod utils;
-
-days! {
-    1 = day1,
-    2 = day2,
-    3 = day3,
-    4 = day4,
-    5 = day5,
-    6 = day6,
-    7 = day7,
-    8 = day8,
-    9 = day9,
-    10 = day10,
-    11 = day11,
-    12 = day12,
-    13 = day13,
-    14 = day14,
-    15 = day15,
-    16 = day16,
-    17 = day17,
-    18 = day18,
-    19 = day19,
-    20 = day20,
-    21 = day21,
-    22 = day22,
-    23 = day23,
-}
+use std::{
+    io::{IsTerminal, Read},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use structopt::{clap::ArgMatches, StructOpt};
+
+use aoc::{
+    examples,
+    harness::{self, Align, RunParams},
+    params::{self, Overrides},
+    picture,
+    solution::{Entry, Registry},
+    trace, utils, visualize, EyreResult, Part,
+};
+
+mod answers;
+mod bench;
+mod config;
+mod download;
+mod history;
+mod leaderboard;
+mod output;
+mod page;
+mod scaffold;
+mod submit;
+
+use answers::{AnswerStore, Verdict};
+use config::{Config, Profile};
+use download::Downloader;
+use output::{Format, SummaryRow};
+use submit::Response;
 
 #[derive(Debug)]
 enum Day {
     Latest,
     Specific(usize),
+    All,
+    Range(RangeInclusive<usize>),
+}
+
+impl Day {
+    fn is_single(&self) -> bool {
+        matches!(self, Day::Latest | Day::Specific(_))
+    }
+
+    fn resolve<'r>(&self, registry: &'r Registry, year: usize) -> EyreResult<Vec<&'r Entry>> {
+        Ok(match self {
+            Day::Latest => vec![registry.latest(year)?],
+            Day::Specific(day) => vec![registry.get(year, *day)?],
+            Day::All => registry.year(year).collect(),
+            Day::Range(range) => registry
+                .year(year)
+                .filter(|entry| range.contains(&entry.day))
+                .collect(),
+        })
+    }
+}
+
+fn parse_day_number(s: &str) -> EyreResult<usize> {
+    s.trim()
+        .trim_start_matches("day")
+        .trim_start_matches(['-', '_'])
+        .parse()
+        .with_context(|| format!("Invalid day: {:?}", s))
 }
 
 impl FromStr for Day {
@@ -50,12 +75,67 @@ impl FromStr for Day {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.trim().to_lowercase().as_str() {
             "latest" => Ok(Self::Latest),
-            other => other
-                .trim_start_matches("day")
-                .trim_start_matches(|c| c == '-' || c == '_')
-                .parse()
-                .map(Self::Specific)
-                .map_err(Into::into),
+            "all" => Ok(Self::All),
+            other => match utils::split2(other, "..") {
+                None => parse_day_number(other).map(Self::Specific),
+                Some((start, end)) => {
+                    let (end, inclusive) = match end.strip_prefix('=') {
+                        Some(end) => (end, true),
+                        None => (end, false),
+                    };
+                    let start = match start.trim() {
+                        "" => 1,
+                        start => parse_day_number(start)?,
+                    };
+                    let end = match end.trim() {
+                        "" => 25,
+                        end if inclusive => parse_day_number(end)?,
+                        end => parse_day_number(end)?
+                            .checked_sub(1)
+                            .ok_or_else(|| eyre::eyre!("Empty range: {}", other))?,
+                    };
+                    Ok(Self::Range(start..=end))
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PartChoice {
+    Single(Part),
+    /// All the parts, sharing a single parse of the input
+    Both,
+}
+
+impl FromStr for PartChoice {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "both" => Ok(Self::Both),
+            other => other.parse().map(Self::Single),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ColorChoice {
+    /// Only when the output is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => eyre::bail!("Unknown color choice: {}", other),
         }
     }
 }
@@ -63,6 +143,7 @@ impl FromStr for Day {
 #[derive(Debug)]
 enum Input {
     Day,
+    Stdin,
     Path(PathBuf),
 }
 
@@ -72,99 +153,1169 @@ impl FromStr for Input {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "day" => Ok(Self::Day),
+            "-" => Ok(Self::Stdin),
             other => Ok(Self::Path(other.parse()?)),
         }
     }
 }
 
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Day => write!(f, "day"),
+            Input::Stdin => write!(f, "stdin"),
+            Input::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Submit the answer of a part, keeping track of the verdicts
+    Submit {
+        #[structopt(short, long, default_value = "latest")]
+        day: Day,
+        /// Part to submit, defaults to the latest one of the day
+        #[structopt(short, long)]
+        part: Option<Part>,
+        /// Answer to submit, computed by running the solution if omitted
+        answer: Option<String>,
+        /// Directory where the submitted answers and their verdicts are stored
+        #[structopt(long, default_value = "submissions")]
+        submissions_dir: PathBuf,
+    },
+    /// Fetch the puzzle of a day, print it and extract its examples
+    Puzzle {
+        #[structopt(short, long)]
+        day: usize,
+        /// Directory where the examples are written
+        #[structopt(long, default_value = "examples")]
+        examples_dir: PathBuf,
+        /// Only print the puzzle
+        #[structopt(long)]
+        no_examples: bool,
+    },
+    /// Show the timings of the previous runs, flagging the ones slower than those before them
+    History {
+        #[structopt(short, long, default_value = "all")]
+        day: Day,
+        #[structopt(short, long)]
+        part: Option<Part>,
+        /// Number of previous runs the median is taken over
+        #[structopt(long, default_value = "5")]
+        window: usize,
+        /// Ratio to the median above which a run is slower
+        #[structopt(long, default_value = "1.5")]
+        factor: f64,
+        /// Number of runs shown for each day and part
+        #[structopt(long, default_value = "10")]
+        last: usize,
+    },
+    /// Show a private leaderboard, which is fetched at most every 15 minutes
+    Leaderboard {
+        /// Id of the leaderboard, the number at the end of its URL
+        #[structopt(required_unless = "file")]
+        id: Option<String>,
+        /// Read the leaderboard from this JSON file instead of fetching it
+        #[structopt(long)]
+        file: Option<PathBuf>,
+    },
+    /// Start the solution of a day from `day_template` and register it
+    New {
+        #[structopt(short, long)]
+        day: usize,
+        /// Title of the puzzle, taken from its page when downloading
+        #[structopt(long)]
+        title: Option<String>,
+        /// Also download the input and the examples of the day
+        #[structopt(long)]
+        download: bool,
+        /// Directory of the sources of the crate
+        #[structopt(long, default_value = "src")]
+        src_dir: PathBuf,
+        /// Directory where the examples are written
+        #[structopt(long, default_value = "examples")]
+        examples_dir: PathBuf,
+    },
+}
+
 #[derive(StructOpt, Debug)]
 struct Args {
-    #[structopt(short, long, possible_values = POSSIBLE_DAYS, default_value = "latest")]
+    #[structopt(subcommand)]
+    command: Option<Command>,
+    /// List the implemented days and exit
+    #[structopt(long)]
+    list: bool,
+    /// Day to run: a number, `latest`, `all` or a range such as `5..=12`
+    #[structopt(short, long, default_value = "latest")]
     day: Day,
-    #[structopt(long, short, possible_values = &["1", "2"])]
-    part: Option<usize>,
+    /// Part to run, `both` runs all the parts on a single parse of the input
+    #[structopt(long, short, possible_values = &["1", "2", "both"])]
+    part: Option<PartChoice>,
     #[structopt(long)]
     no_timings: bool,
-    #[structopt(short, long, default_value = "day")]
-    input: Input,
-    #[structopt(long, short, env = "AOC_SESSION")]
+    /// Output format of the results: text, json or csv
+    #[structopt(long, default_value = "text")]
+    format: Format,
+    /// Input to run on: `day` for the input of the day, `-` for stdin or a path. Given several
+    /// times, the answers of each input are shown side by side
+    #[structopt(short, long, default_value = "day", number_of_values = 1)]
+    input: Vec<Input>,
+    /// Profile of the configuration files to use instead of the default one
+    #[structopt(long, env = "AOC_PROFILE", global = true)]
+    profile: Option<String>,
+    #[structopt(long, short, env = "AOC_SESSION", global = true)]
     session: Option<String>,
-    #[structopt(long)]
+    #[structopt(long, global = true)]
     skip_dl: bool,
-    #[structopt(long, default_value = "input")]
+    /// Download the inputs again even if they are already present
+    #[structopt(long, conflicts_with = "skip-dl")]
+    refresh: bool,
+    /// Address of the Advent of Code website
+    #[structopt(
+        long,
+        env = "AOC_BASE_URL",
+        default_value = download::DEFAULT_BASE_URL,
+        global = true
+    )]
+    base_url: String,
+    #[structopt(long, default_value = "input", global = true)]
     dl_dir: PathBuf,
-    #[structopt(long, short)]
+    /// Calendar to run, defaults to the latest one with solutions that has started
+    #[structopt(long, short, global = true)]
     year: Option<usize>,
+    /// Compare the answers with the ones stored in the answers directory
+    #[structopt(long, conflicts_with = "record")]
+    check: bool,
+    /// Store the answers in the answers directory as the expected ones
+    #[structopt(long)]
+    record: bool,
+    #[structopt(long, default_value = "answers")]
+    answers_dir: PathBuf,
+    /// Benchmark the solutions by running them this many times
+    #[structopt(long)]
+    bench: Option<usize>,
+    /// Runs done before measuring when benchmarking
+    #[structopt(long, default_value = "3")]
+    warmup: usize,
+    /// Compare the benchmark medians with the ones stored in this file
+    #[structopt(long)]
+    baseline: Option<PathBuf>,
+    /// Store the benchmark medians in this file
+    #[structopt(long)]
+    save_baseline: Option<PathBuf>,
+    /// Slowdown (in percent) compared to the baseline that counts as a regression
+    #[structopt(long, default_value = "10")]
+    threshold: f64,
+    /// Give up on a run after this long, such as `30s` or `2m`
+    #[structopt(long, parse(try_from_str = humantime::parse_duration), global = true)]
+    timeout: Option<Duration>,
+    /// Value of a parameter of the solutions, as `name=value`, see `--list` for the parameters
+    #[structopt(long = "param", parse(try_from_str = params::parse_override), number_of_values = 1)]
+    params: Vec<(String, String)>,
+    /// Animate the state of the solutions that support it on stderr
+    #[structopt(long)]
+    visualize: bool,
+    /// Frames shown per second when visualizing
+    #[structopt(long, default_value = "10")]
+    fps: f64,
+    /// When to color the visualization: auto, always or never
+    #[structopt(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    color: ColorChoice,
+    /// Save the state drawn by the solutions that support it as a .ppm, .png or .svg image,
+    /// `{day}` and `{part}` in the path are replaced to export several runs
+    #[structopt(long, conflicts_with = "bench")]
+    export: Option<PathBuf>,
+    /// Size in pixels of the cells of the exported images
+    #[structopt(long, default_value = "1")]
+    scale: usize,
+    /// Colours of the exported images
+    #[structopt(long, default_value = "viridis", possible_values = picture::Palette::NAMES)]
+    palette: picture::Palette,
+    /// Explain how the answers are reached on stderr, `-vv` also shows each intermediate step
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+    /// File where the timings of the runs are added, see the `history` command
+    #[structopt(
+        long,
+        env = "AOC_HISTORY",
+        default_value = "history.jsonl",
+        global = true
+    )]
+    history: PathBuf,
+    /// Don't add the timings of the runs to the history
+    #[structopt(long)]
+    no_history: bool,
+    /// Number of runs done at the same time when running several days, 0 uses one per CPU.
+    /// Defaults to 1
+    #[structopt(short, long)]
+    jobs: Option<usize>,
 }
 
-fn main() -> EyreResult<()> {
-    color_eyre::install()?;
-    let args = Args::from_args();
+/// Year of the latest calendar that has started, puzzles unlock at midnight EST
+fn current_season() -> usize {
+    let now = chrono::Utc::now().with_timezone(&chrono::FixedOffset::west(5 * 3600));
+    if now.month() == 12 {
+        now.year() as usize
+    } else {
+        now.year() as usize - 1
+    }
+}
+
+/// Takes the settings that were not given on the command line from the profile
+fn apply_profile(args: &mut Args, matches: &ArgMatches, profile: &Profile) {
+    let given = |name| matches.occurrences_of(name) != 0;
+    if args.session.is_none() {
+        args.session = profile.session.clone();
+    }
+    if args.year.is_none() {
+        args.year = profile.year;
+    }
+    match &profile.dl_dir {
+        Some(dl_dir) if !given("dl-dir") => args.dl_dir = dl_dir.clone(),
+        _ => (),
+    }
+    match profile.format {
+        Some(format) if !given("format") => args.format = format,
+        _ => (),
+    }
+    if profile.timings == Some(false) {
+        args.no_timings = true;
+    }
+}
 
-    let day = resolve_day(args.day);
+fn input_path(args: &Args, year: usize, day: usize) -> PathBuf {
+    let mut path = args.dl_dir.clone();
+    path.push(year.to_string());
+    path.push(format!("day{}", day));
+    path
+}
 
-    let input = match args.input {
+fn load_input(entry: &Entry, input: &Input, args: &Args) -> EyreResult<String> {
+    let (year, day) = (entry.year, entry.day);
+    match input {
         Input::Day => {
-            let mut path = args.dl_dir;
-            path.push(format!("day{}", day));
-            if !path.exists() {
+            let path = input_path(args, year, day);
+            if args.refresh || !download::is_cached(&path) {
                 if args.skip_dl {
-                    eyre::bail!("Input for current day is not downloaded and skip-dl = true");
+                    let mut legacy = args.dl_dir.clone();
+                    legacy.push(format!("day{}", day));
+                    if legacy.exists() {
+                        eyre::bail!(
+                            "Input for day {} of {} is not downloaded and skip-dl = true, \
+                             move {:?} to {:?} if it is the input of {}",
+                            day,
+                            year,
+                            legacy,
+                            path,
+                            year
+                        );
+                    }
+                    eyre::bail!(
+                        "Input for day {} of {} is not downloaded and skip-dl = true",
+                        day,
+                        year
+                    );
                 }
 
-                let session = match args.session {
+                let session = match &args.session {
                     None => eyre::bail!("No session provided while needing to download input"),
                     Some(s) => s,
                 };
 
-                let year = args
-                    .year
-                    .unwrap_or_else(|| chrono::Local::now().year() as usize);
+                return Downloader::new(&args.base_url, Some(session))?
+                    .fetch_input(year, day, &path);
+            }
+            Ok(std::fs::read_to_string(path)?)
+        }
+        Input::Stdin => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .context("Could not read the input from stdin")?;
+            Ok(input)
+        }
+        Input::Path(p) => std::fs::read_to_string(p)
+            .with_context(|| format!("Could not read the input at {:?}", p)),
+    }
+}
 
-                let client = reqwest::blocking::Client::new();
-                let body = client
-                    .get(&format!(
-                        "https://adventofcode.com/{}/day/{}/input",
-                        year, day
-                    ))
-                    .header("Cookie", format!("session={}", session))
-                    .send()
-                    .with_context(|| {
-                        format!("Could not fetch the input for day {} of AoC {}", day, year)
-                    })?
-                    .error_for_status()
-                    .with_context(|| {
-                        format!("Error accessing the input for day {} of AoC {}", day, year)
-                    })?
-                    .text()
-                    .with_context(|| "Error reading the body of the response")?;
-
-                let mut writer = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .open(&path)
-                    .with_context(|| format!("Could not open file at {:?}", path))?;
-
-                writer
-                    .write_all(body.as_bytes())
-                    .with_context(|| format!("Could not write to file {:?}", path))?;
+/// Parts to run for a day: the requested one, the latest one for a single day or all of them
+fn selected_parts(entry: &Entry, part: Option<PartChoice>, single: bool) -> Vec<Part> {
+    match part {
+        Some(PartChoice::Single(part)) => vec![part],
+        Some(PartChoice::Both) => entry.parts.to_vec(),
+        None if single => vec![entry.latest_part()],
+        None => entry.parts.to_vec(),
+    }
+}
+
+/// Checks or records the answer of a run, depending on the arguments
+fn handle_answer(
+    year: usize,
+    day: usize,
+    part: Part,
+    answer: &str,
+    store: &AnswerStore,
+    args: &Args,
+) -> EyreResult<Option<Verdict>> {
+    if args.record {
+        store.record(year, day, part, answer)?;
+        Ok(None)
+    } else if args.check {
+        store.check(year, day, part, answer).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn print_row(row: &SummaryRow, single: bool, args: &Args, params: &RunParams) {
+    match &row.outcome {
+        Ok(outcome) if single => println!(
+            "Day {} Part {}:\n  {}",
+            row.day,
+            row.part,
+            harness::render(outcome, params.timings)
+        ),
+        Ok(outcome) => println!("Day {} Part {}:\n  {}", row.day, row.part, outcome.message),
+        Err(e) => println!("Day {} Part {}:\n  Error: {:#}", row.day, row.part, e),
+    }
+    match &row.verdict {
+        Some(Verdict::Fail { expected }) => println!("  Check: FAIL, expected {}", expected),
+        Some(verdict) => println!("  Check: {}", verdict),
+        None if args.record && row.outcome.is_ok() => {
+            println!("  Recorded the answer of day {} part {}", row.day, row.part)
+        }
+        None => (),
+    }
+}
+
+/// Settings shared by all the runs
+#[derive(Clone)]
+struct RunOptions {
+    timeout: Option<Duration>,
+    params: Overrides,
+    visualize: Option<visualize::Settings>,
+    export: bool,
+    verbosity: u8,
+}
+
+impl RunOptions {
+    fn new(args: &Args) -> Self {
+        let terminal = std::io::stderr().is_terminal();
+        let visualize = args.visualize.then(|| visualize::Settings {
+            delay: Duration::from_secs_f64(1. / args.fps),
+            color: match args.color {
+                ColorChoice::Auto => terminal,
+                ColorChoice::Always => true,
+                ColorChoice::Never => false,
+            },
+            in_place: terminal,
+        });
+        Self {
+            timeout: args.timeout,
+            params: args.params.iter().cloned().collect(),
+            visualize,
+            export: args.export.is_some(),
+            verbosity: args.verbose,
+        }
+    }
+
+    /// Runs `f` with the parameters, the visualization, the pictures and the trace, on a worker
+    /// thread when there is a timeout
+    fn run<O, F>(&self, f: F) -> EyreResult<O>
+    where
+        O: Send + 'static,
+        F: FnOnce() -> EyreResult<O> + Send + 'static,
+    {
+        let (params, visualize) = (self.params.clone(), self.visualize);
+        let (export, verbosity) = (self.export, self.verbosity);
+        harness::with_timeout(self.timeout, move || {
+            params::scope(&params, || {
+                visualize::scope(visualize, || {
+                    picture::scope(export, || trace::scope(verbosity, f))
+                })
+            })
+        })
+    }
+}
+
+/// Checks that each parameter is one of a day to run, and that its value suits all of them
+fn check_params(days: &[&Entry], overrides: &Overrides) -> EyreResult<()> {
+    for (name, value) in overrides {
+        let params: Vec<_> = days
+            .iter()
+            .flat_map(|entry| entry.params)
+            .filter(|param| param.name() == name)
+            .collect();
+        if params.is_empty() {
+            let known: Vec<_> = days
+                .iter()
+                .flat_map(|entry| {
+                    entry.params.iter().map(move |param| {
+                        format!(
+                            "\n  - {} (day {}): {}",
+                            param.name(),
+                            entry.day,
+                            param.help()
+                        )
+                    })
+                })
+                .collect();
+            match &known[..] {
+                [] => eyre::bail!("No day to run has parameters"),
+                _ => eyre::bail!(
+                    "No day to run has a parameter named {:?}, the parameters are:{}",
+                    name,
+                    known.concat()
+                ),
             }
-            std::fs::read_to_string(path)?
         }
-        Input::Path(p) => std::fs::read_to_string(p)?,
+        for param in params {
+            param.check(value)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_part(
+    entry: &Entry,
+    part: Part,
+    input: &str,
+    options: &RunOptions,
+) -> EyreResult<harness::Outcome> {
+    let (entry, input) = (*entry, input.to_string());
+    options.run(move || entry.run(part, &input))
+}
+
+/// Runs the parts of a day, giving one outcome per part
+fn run_day(
+    entry: &Entry,
+    parts: &[Part],
+    part: Option<PartChoice>,
+    input: &EyreResult<String>,
+    options: &RunOptions,
+) -> Vec<(Part, EyreResult<harness::Outcome>)> {
+    let failed = |e: &color_eyre::Report| {
+        parts
+            .iter()
+            .map(|&part| (part, Err(eyre::eyre!("{:#}", e))))
+            .collect()
     };
 
-    run_solution(
-        day,
-        args.part.map(|v| match v {
-            1 => Part::Part1,
-            2 => Part::Part2,
-            _ => unreachable!(),
-        }),
-        &input,
-        RunParams {
-            timings: !args.no_timings,
+    match (input, part) {
+        (Err(e), _) => failed(e),
+        (Ok(input), Some(PartChoice::Both)) => {
+            let (all, input) = (*entry, input.clone());
+            match options.run(move || all.run_all_parts(&input)) {
+                Ok(outcomes) => parts.iter().copied().zip(outcomes).collect(),
+                Err(e) => failed(&e),
+            }
+        }
+        (Ok(input), _) => parts
+            .iter()
+            .map(|&part| (part, run_part(entry, part, input, options)))
+            .collect(),
+    }
+}
+
+/// The only input to run on, which must be the one of the day when running several days
+fn single_input(args: &Args, several_days: bool) -> EyreResult<&Input> {
+    match &args.input[..] {
+        [input] if !several_days || matches!(input, Input::Day) => Ok(input),
+        [_] => eyre::bail!("A single input file can't be used for several days"),
+        _ => eyre::bail!("Several inputs can only be used to run the solution of a day"),
+    }
+}
+
+/// Runs a day on each input, showing the answers side by side
+fn run_inputs(entry: &Entry, part: Option<PartChoice>, args: &Args) -> EyreResult<()> {
+    if args.check || args.record {
+        eyre::bail!("Answers can't be checked or recorded with several inputs");
+    }
+    if args.export.is_some() {
+        eyre::bail!("Pictures can't be exported with several inputs");
+    }
+    if args
+        .input
+        .iter()
+        .filter(|i| matches!(i, Input::Stdin))
+        .count()
+        > 1
+    {
+        eyre::bail!("Stdin can only be read once");
+    }
+
+    let parts = selected_parts(entry, part, true);
+    let options = RunOptions::new(args);
+    let columns: Vec<Vec<SummaryRow>> = args
+        .input
+        .iter()
+        .map(|input| {
+            let content = load_input(entry, input, args);
+            run_day(entry, &parts, part, &content, &options)
+                .into_iter()
+                .map(|(part, outcome)| SummaryRow {
+                    year: entry.year,
+                    day: entry.day,
+                    part,
+                    input: Some(input.to_string()),
+                    outcome,
+                    verdict: None,
+                })
+                .collect()
+        })
+        .collect();
+
+    if args.format == Format::Text {
+        println!("{}", output::render_side_by_side(&columns));
+    }
+    let rows: Vec<SummaryRow> = columns.into_iter().flatten().collect();
+    match args.format {
+        Format::Text => (),
+        Format::Json => println!("{}", output::render_json(&rows)?),
+        Format::Csv => print!("{}", output::render_csv(&rows)),
+    }
+
+    let failures = rows.iter().filter(|row| row.outcome.is_err()).count();
+    if failures != 0 {
+        eyre::bail!("{} run(s) failed", failures);
+    }
+    Ok(())
+}
+
+/// Checks that the exported images have a known format and a different path for each run
+fn check_export(path: &Path, days: &[(&Entry, Vec<Part>, EyreResult<String>)]) -> EyreResult<()> {
+    picture::Format::from_path(path)?;
+    let path = path.to_string_lossy();
+    if days.len() > 1 && !path.contains("{day}") {
+        eyre::bail!("Use {} in the export path to export several days", "{day}");
+    }
+    if days.iter().any(|(_, parts, _)| parts.len() > 1) && !path.contains("{part}") {
+        eyre::bail!(
+            "Use {} in the export path to export several parts",
+            "{part}"
+        );
+    }
+    Ok(())
+}
+
+/// Saves the picture of a run to the export path, with the day and part filled in
+fn export(
+    row: &SummaryRow,
+    picture: &picture::Picture,
+    path: &Path,
+    args: &Args,
+) -> EyreResult<()> {
+    let path = PathBuf::from(
+        path.to_string_lossy()
+            .replace("{day}", &row.day.to_string())
+            .replace("{part}", &row.part.to_string()),
+    );
+    let content = picture::Format::from_path(&path)?.encode(picture, &args.palette, args.scale);
+    download::write_atomic(&path, &content)?;
+    eprintln!("Exported day {} part {} to {:?}", row.day, row.part, path);
+    Ok(())
+}
+
+/// Adds the timings of the successful runs to the history, unless the way the solutions were run
+/// makes them incomparable with the other runs
+fn record_history(
+    rows: &[SummaryRow],
+    days: &[(&Entry, Vec<Part>, EyreResult<String>)],
+    args: &Args,
+) -> EyreResult<()> {
+    let altered = !args.params.is_empty()
+        || args.visualize
+        || args.export.is_some()
+        || args.verbose != 0
+        || aoc::memory::ENABLED;
+    if args.no_history || altered {
+        return Ok(());
+    }
+
+    let (ran_at, commit, machine) = (
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        history::current_commit(),
+        history::machine(),
+    );
+    let mut runs = Vec::new();
+    for row in rows {
+        let day = days
+            .iter()
+            .find(|(entry, _, _)| entry.year == row.year && entry.day == row.day);
+        let (outcome, (_, _, input)) = match (&row.outcome, day) {
+            (Ok(outcome), Some(day)) => (outcome, day),
+            _ => continue,
+        };
+        let input = match input {
+            Ok(input) => input,
+            Err(_) => continue,
+        };
+        runs.push(history::Run {
+            ran_at: ran_at.clone(),
+            commit: commit.clone(),
+            machine: machine.clone(),
+            input: history::hash_input(input),
+            year: row.year,
+            day: row.day,
+            part: row.part.to_string(),
+            parse_ns: outcome.timings.parse.map(|d| d.as_nanos()),
+            inter_ns: outcome.timings.inter.map(|d| d.as_nanos()),
+            solve_ns: outcome.timings.part.as_nanos(),
+        });
+    }
+    if runs.is_empty() {
+        return Ok(());
+    }
+    history::append(&args.history, &runs)
+}
+
+/// Shows the history of the timings of `days`
+fn show_history(
+    days: &[&Entry],
+    part: Option<Part>,
+    settings: history::Settings,
+    args: &Args,
+) -> EyreResult<()> {
+    let runs = history::load(&args.history)?;
+    let part = part.map(|part| part.to_string());
+    let runs: Vec<_> = runs
+        .iter()
+        .filter(|run| {
+            days.iter()
+                .any(|entry| entry.year == run.year && entry.day == run.day)
+                && part.as_ref().is_none_or(|part| *part == run.part)
+        })
+        .collect();
+    if runs.is_empty() {
+        println!("No runs in the history at {:?}", args.history);
+        return Ok(());
+    }
+
+    let (out, regressions) = history::render(&runs, settings);
+    println!("{}", out);
+    if regressions != 0 {
+        eyre::bail!(
+            "The latest run of {} day(s) and part(s) is slower",
+            regressions
+        );
+    }
+    Ok(())
+}
+
+fn run(days: &[&Entry], part: Option<PartChoice>, single: bool, args: &Args) -> EyreResult<()> {
+    if args.input.len() > 1 && single {
+        return run_inputs(days[0], part, args);
+    }
+    let input = single_input(args, !single)?;
+    let params = RunParams {
+        timings: !args.no_timings,
+    };
+
+    let jobs = match args.jobs.unwrap_or(1) {
+        0 => std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        jobs => jobs,
+    };
+
+    // The inputs are loaded beforehand so that the downloads are not done concurrently
+    let days: Vec<_> = days
+        .iter()
+        .map(|entry| {
+            let parts = selected_parts(entry, part, single);
+            (*entry, parts, load_input(entry, input, args))
+        })
+        .collect();
+    if let Some(path) = &args.export {
+        check_export(path, &days)?;
+    }
+
+    let store = AnswerStore::new(args.answers_dir.clone());
+    let mut rows = Vec::new();
+    let options = RunOptions::new(args);
+    let start = Instant::now();
+    harness::run_ordered(
+        jobs,
+        &days,
+        |(entry, parts, input)| {
+            (
+                entry.year,
+                entry.day,
+                run_day(entry, parts, part, input, &options),
+            )
+        },
+        |(year, day, outcomes)| {
+            for (part, outcome) in outcomes {
+                let outcome = match outcome {
+                    Err(e) if single && args.format == Format::Text => return Err(e),
+                    outcome => outcome,
+                };
+                let verdict = match &outcome {
+                    Ok(outcome) => handle_answer(year, day, part, &outcome.answer, &store, args)?,
+                    Err(_) => None,
+                };
+                let row = SummaryRow {
+                    year,
+                    day,
+                    part,
+                    input: None,
+                    outcome,
+                    verdict,
+                };
+                if args.format == Format::Text {
+                    print_row(&row, single, args, &params);
+                }
+                if let (Some(path), Ok(outcome)) = (&args.export, &row.outcome) {
+                    match &outcome.picture {
+                        Some(picture) => export(&row, picture, path, args)?,
+                        None => {
+                            eprintln!("Day {} part {} has no picture to export", row.day, row.part)
+                        }
+                    }
+                }
+                rows.push(row);
+            }
+            Ok(())
         },
     )?;
+    let wall = start.elapsed();
+
+    match args.format {
+        Format::Text if single => (),
+        Format::Text => println!(
+            "\n{}\nWall time: {:.2?} with {} job(s)",
+            output::render_summary(&rows),
+            wall,
+            jobs
+        ),
+        Format::Json => println!("{}", output::render_json(&rows)?),
+        Format::Csv => print!("{}", output::render_csv(&rows)),
+    }
+    record_history(&rows, &days, args)?;
+
+    let failures = rows.iter().filter(|row| row.outcome.is_err()).count();
+    if failures != 0 {
+        eyre::bail!("{} run(s) failed", failures);
+    }
+    let mismatches = rows
+        .iter()
+        .filter(|row| matches!(row.verdict, Some(Verdict::Fail { .. })))
+        .count();
+    if mismatches != 0 {
+        eyre::bail!("{} answer(s) did not match", mismatches);
+    }
     Ok(())
 }
+
+fn run_bench(
+    days: &[&Entry],
+    part: Option<PartChoice>,
+    iterations: usize,
+    args: &Args,
+) -> EyreResult<()> {
+    let input = single_input(args, days.len() > 1)?;
+
+    let baseline = args
+        .baseline
+        .as_deref()
+        .map(bench::Baseline::load)
+        .transpose()?;
+
+    let options = RunOptions::new(args);
+    let mut results = Vec::new();
+    for entry in days {
+        let (year, day) = (entry.year, entry.day);
+        let input = load_input(entry, input, args)?;
+        for part in selected_parts(entry, part, days.len() == 1) {
+            eprintln!("Benchmarking day {} part {}", day, part);
+            results.push(bench::bench(
+                year,
+                day,
+                part,
+                iterations,
+                args.warmup,
+                || run_part(entry, part, &input, &options),
+            )?);
+        }
+    }
+
+    let (table, regressions) = bench::render(&results, baseline.as_ref(), args.threshold);
+    println!("{}", table);
+
+    if let Some(path) = &args.save_baseline {
+        bench::Baseline::save(path, &results)?;
+    }
+    if regressions != 0 {
+        eyre::bail!(
+            "{} timing(s) regressed by more than {}%",
+            regressions,
+            args.threshold
+        );
+    }
+    Ok(())
+}
+
+fn submit(
+    entry: &Entry,
+    part: Option<Part>,
+    answer: Option<&str>,
+    submissions_dir: &Path,
+    args: &Args,
+) -> EyreResult<()> {
+    let (year, day) = (entry.year, entry.day);
+    let part = part.unwrap_or_else(|| entry.latest_part());
+    let answer = match answer {
+        Some(answer) => answer.to_string(),
+        None => {
+            let input = single_input(args, false)?;
+            let input = load_input(entry, input, args)?;
+            if !args.params.is_empty() {
+                eyre::bail!("Answers computed with changed parameters can't be submitted");
+            }
+            let outcome = run_part(entry, part, &input, &RunOptions::new(args))?;
+            println!("Day {} Part {}:\n  {}", day, part, outcome.message);
+            outcome.answer
+        }
+    };
+
+    let session = match &args.session {
+        None => eyre::bail!("No session provided while needing to submit an answer"),
+        Some(s) => s,
+    };
+    let client = Downloader::new(&args.base_url, Some(session))?;
+    match submit::submit(&client, submissions_dir, year, day, part, &answer)? {
+        Response::Verdict(submit::Verdict::Correct) => {
+            println!(
+                "{} is the right answer for day {} part {}",
+                answer, day, part
+            );
+            Ok(())
+        }
+        Response::Verdict(verdict) => {
+            eyre::bail!("{} is not the right answer, it is {}", answer, verdict)
+        }
+        Response::RateLimited(Some(wait)) => eyre::bail!(
+            "An answer was submitted too recently, {} left to wait",
+            humantime::format_duration(wait)
+        ),
+        Response::RateLimited(None) => eyre::bail!("An answer was submitted too recently"),
+        Response::WrongLevel => eyre::bail!(
+            "Part {} of day {} is already solved or not unlocked yet",
+            part,
+            day
+        ),
+    }
+}
+
+fn puzzle(
+    year: usize,
+    day: usize,
+    examples_dir: &Path,
+    no_examples: bool,
+    args: &Args,
+) -> EyreResult<()> {
+    let client = Downloader::new(&args.base_url, args.session.as_deref())?;
+    let html = client.get(&format!("/{}/day/{}", year, day))?;
+    let puzzle = page::parse(&html, std::io::stdout().is_terminal());
+    println!("{}", puzzle.text);
+
+    if no_examples {
+        return Ok(());
+    }
+    save_examples(&examples::year_dir(examples_dir, year), day, &puzzle)
+}
+
+/// Writes the examples of a puzzle, showing the answers it suggests for them
+fn save_examples(examples_dir: &Path, day: usize, puzzle: &page::Puzzle) -> EyreResult<()> {
+    let written = page::write_examples(examples_dir, day, &puzzle.examples)?;
+    for (index, example) in puzzle.examples.iter().enumerate() {
+        let path = page::example_path(examples_dir, day, index);
+        let written = written.contains(&path);
+        if written {
+            eprintln!("Wrote the example {:?}", path);
+        }
+        for (part, answer) in [Part::Part1, Part::Part2].iter().zip(&example.answers) {
+            if let Some(answer) = answer {
+                eprintln!("  Suggested answer for part {}: {}", part, answer);
+            }
+        }
+        if written && example.answers.iter().any(Option::is_some) {
+            eprintln!(
+                "  Check the suggested answers in {:?}",
+                examples::sidecar_path(&path)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn show_leaderboard(
+    year: usize,
+    id: Option<&str>,
+    file: Option<&Path>,
+    args: &Args,
+) -> EyreResult<()> {
+    let (json, id) = match (file, id) {
+        (Some(file), id) => (
+            std::fs::read_to_string(file)
+                .with_context(|| format!("Could not read the leaderboard at {:?}", file))?,
+            id.map_or_else(|| file.display().to_string(), String::from),
+        ),
+        (None, None) => eyre::bail!("A leaderboard id or file is needed"),
+        (None, Some(id)) => {
+            leaderboard::check_id(id)?;
+            let mut cache = args.dl_dir.clone();
+            cache.push(year.to_string());
+            cache.push(format!("leaderboard-{}.json", id));
+            if args.skip_dl {
+                let json = std::fs::read_to_string(&cache)
+                    .with_context(|| format!("The leaderboard {} was not fetched yet", id))?;
+                (json, id.to_string())
+            } else {
+                let session = match &args.session {
+                    None => eyre::bail!("No session provided while needing to fetch a leaderboard"),
+                    Some(s) => s,
+                };
+                let client = Downloader::new(&args.base_url, Some(session))?;
+                (
+                    leaderboard::fetch(&client, year, id, &cache)?,
+                    id.to_string(),
+                )
+            }
+        }
+    };
+    println!("{}", leaderboard::render(&leaderboard::parse(&json)?, &id));
+    Ok(())
+}
+
+fn new_day(
+    year: usize,
+    day: usize,
+    title: Option<&str>,
+    download: bool,
+    src_dir: &Path,
+    examples_dir: &Path,
+    args: &Args,
+) -> EyreResult<()> {
+    if !(1..=25).contains(&day) {
+        eyre::bail!("There is no day {} in a calendar", day);
+    }
+    // The days of another calendar than the default one are in a module of their own
+    let module_dir = match scaffold::year_module(year) {
+        Some(module) => src_dir.join(module),
+        None => src_dir.to_path_buf(),
+    };
+    let path = module_dir.join(format!("day{}.rs", day));
+    if path.exists() {
+        eyre::bail!("{:?} already exists", path);
+    }
+    let module = scaffold::year_module(year)
+        .map(|_| -> EyreResult<_> {
+            let path = module_dir.join("mod.rs");
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => scaffold::add_module(&content, &format!("day{}", day))?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    format!(
+                        "//! Solutions to the puzzles of {}\n\npub mod day{};\n",
+                        year, day
+                    )
+                }
+                Err(e) => return Err(e).with_context(|| format!("Could not read {:?}", path)),
+            };
+            Ok((path, content))
+        })
+        .transpose()?;
+    let lib_path = src_dir.join("lib.rs");
+    let template_path = src_dir.join("day_template");
+    let template = std::fs::read_to_string(&template_path)
+        .with_context(|| format!("Could not read the template at {:?}", template_path))?;
+    let lib = std::fs::read_to_string(&lib_path)
+        .with_context(|| format!("Could not read {:?}", lib_path))?;
+    let lib = scaffold::register(&lib, year, day)?;
+
+    // Downloading first, so that a failure leaves no half created day behind
+    let mut title = title.map(String::from);
+    if download {
+        let session = match &args.session {
+            None => eyre::bail!("No session provided while needing to download input"),
+            Some(s) => s,
+        };
+        let client = Downloader::new(&args.base_url, Some(session))?;
+        let input = input_path(args, year, day);
+        client.fetch_input(year, day, &input)?;
+        eprintln!("Downloaded the input to {:?}", input);
+
+        let html = client.get(&format!("/{}/day/{}", year, day))?;
+        let puzzle = page::parse(&html, false);
+        title = title.or_else(|| puzzle.title.clone());
+        save_examples(&examples::year_dir(examples_dir, year), day, &puzzle)?;
+    }
+
+    let content = scaffold::render(&template, year, day, title.as_deref().unwrap_or_default());
+    download::write_atomic(&path, content.as_bytes())?;
+    if let Some((path, content)) = &module {
+        download::write_atomic(path, content.as_bytes())?;
+    }
+    download::write_atomic(&lib_path, lib.as_bytes())?;
+    eprintln!("Created {:?} and registered it in {:?}", path, lib_path);
+    Ok(())
+}
+
+fn list(registry: &Registry) -> String {
+    let rows: Vec<_> = registry
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.year.to_string(),
+                entry.day.to_string(),
+                entry.title.to_string(),
+                entry
+                    .parts
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                entry
+                    .params
+                    .iter()
+                    .map(|param| format!("{}={}", param.name(), param.defaults()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ]
+        })
+        .collect();
+    harness::render_table(
+        &[
+            ("Year", Align::Right),
+            ("Day", Align::Right),
+            ("Title", Align::Left),
+            ("Parts", Align::Left),
+            ("Parameters", Align::Left),
+        ],
+        &rows,
+    )
+}
+
+fn main() -> EyreResult<()> {
+    color_eyre::install()?;
+    let matches = Args::clap().get_matches();
+    let mut args = Args::from_clap(&matches);
+    if let Some(profile) = Config::load()?.profile(args.profile.as_deref())? {
+        apply_profile(&mut args, &matches, profile);
+    }
+
+    let registry = aoc::registry();
+    if args.list {
+        println!("{}", list(&registry));
+        return Ok(());
+    }
+
+    let year = match args.year {
+        Some(year) => year,
+        None => {
+            let season = current_season();
+            registry.latest_year(season).unwrap_or(season)
+        }
+    };
+    match &args.command {
+        Some(Command::Submit {
+            day,
+            part,
+            answer,
+            submissions_dir,
+        }) => {
+            if !day.is_single() {
+                eyre::bail!("Answers can only be submitted for a single day");
+            }
+            let entry = day.resolve(&registry, year)?[0];
+            submit(entry, *part, answer.as_deref(), submissions_dir, &args)
+        }
+        Some(Command::Puzzle {
+            day,
+            examples_dir,
+            no_examples,
+        }) => puzzle(year, *day, examples_dir, *no_examples, &args),
+        Some(Command::History {
+            day,
+            part,
+            window,
+            factor,
+            last,
+        }) => {
+            if *window == 0 || !(factor.is_finite() && *factor > 0.) {
+                eyre::bail!("The window and the factor of the history must be positive");
+            }
+            let days = day.resolve(&registry, year)?;
+            let settings = history::Settings {
+                window: *window,
+                factor: *factor,
+                last: *last,
+            };
+            show_history(&days, *part, settings, &args)
+        }
+        Some(Command::Leaderboard { id, file }) => {
+            show_leaderboard(year, id.as_deref(), file.as_deref(), &args)
+        }
+        Some(Command::New {
+            day,
+            title,
+            download,
+            src_dir,
+            examples_dir,
+        }) => new_day(
+            year,
+            *day,
+            title.as_deref(),
+            *download,
+            src_dir,
+            examples_dir,
+            &args,
+        ),
+        None => run_days(&registry, year, &args),
+    }
+}
+
+/// Runs or benchmarks the selected days, when no subcommand is given
+fn run_days(registry: &Registry, year: usize, args: &Args) -> EyreResult<()> {
+    if !(args.fps.is_finite() && args.fps > 0.) {
+        eyre::bail!("The frame rate must be positive, got {}", args.fps);
+    }
+    if args.scale == 0 {
+        eyre::bail!("The exported images need a scale of at least 1");
+    }
+    if args.visualize && args.jobs.unwrap_or(1) != 1 {
+        eyre::bail!("The runs can only be visualized one at a time");
+    }
+
+    let days = args.day.resolve(registry, year)?;
+    check_params(&days, &RunOptions::new(args).params)?;
+    if !args.params.is_empty() && (args.check || args.record) {
+        eyre::bail!("Answers can't be checked or recorded with changed parameters");
+    }
+    if let Some(iterations) = args.bench {
+        if args.jobs.is_some() {
+            eyre::bail!("The benchmarks run one day at a time, --jobs can't be used with --bench");
+        }
+        return run_bench(&days, args.part, iterations, args);
+    }
+    run(&days, args.part, args.day.is_single(), args)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Day;
+
+    #[test]
+    fn parse_day_ranges() {
+        let range = |s: &str| match s.parse().unwrap() {
+            Day::Range(r) => r,
+            other => panic!("{:?} is not a range", other),
+        };
+        assert_eq!(range("5..=12"), 5..=12);
+        assert_eq!(range("5..12"), 5..=11);
+        assert_eq!(range("day3.."), 3..=25);
+        assert_eq!(range("..=4"), 1..=4);
+        assert!(matches!("all".parse().unwrap(), Day::All));
+        assert!(matches!("day-7".parse().unwrap(), Day::Specific(7)));
+    }
+}
@@ -0,0 +1,202 @@
+//! Heap use of the solutions, counted by a global allocator when the `alloc-stats` feature is
+//! enabled
+//!
+//! The counters are kept per thread, so a run only accounts for what its own thread allocated.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Heap use of a step of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AllocStats {
+    /// Largest amount of memory held at once during the step, on top of what was held before it
+    pub peak: usize,
+    /// Bytes allocated during the step, reallocations count as allocations of their new size
+    pub total: usize,
+    /// Number of allocations and reallocations
+    pub count: usize,
+}
+
+/// Byte count displayed with a binary unit
+pub struct Bytes(pub usize);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.;
+        let mut unit = 0;
+        while value >= 1024. && unit + 1 < UNITS.len() {
+            value /= 1024.;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", value, UNITS[unit])
+    }
+}
+
+impl Display for AllocStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "peak {}, {} in {} allocations",
+            Bytes(self.peak),
+            Bytes(self.total),
+            self.count
+        )
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Counters {
+        /// Can go below zero when memory allocated by another thread is freed
+        pub(super) current: isize,
+        pub(super) peak: isize,
+        pub(super) total: usize,
+        pub(super) count: usize,
+    }
+
+    thread_local! {
+        pub(super) static COUNTERS: Cell<Counters> = const {
+            Cell::new(Counters {
+                current: 0,
+                peak: 0,
+                total: 0,
+                count: 0,
+            })
+        };
+    }
+
+    fn record(delta: isize, allocated: Option<usize>) {
+        // The counters are gone while the thread is being torn down
+        let _ = COUNTERS.try_with(|counters| {
+            let mut c = counters.get();
+            c.current += delta;
+            c.peak = c.peak.max(c.current);
+            if let Some(size) = allocated {
+                c.total += size;
+                c.count += 1;
+            }
+            counters.set(c);
+        });
+    }
+
+    struct Counting;
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                record(layout.size() as isize, Some(layout.size()));
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                record(layout.size() as isize, Some(layout.size()));
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            record(-(layout.size() as isize), None);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new = System.realloc(ptr, layout, new_size);
+            if !new.is_null() {
+                record(new_size as isize - layout.size() as isize, Some(new_size));
+            }
+            new
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+}
+
+/// Whether the allocations are counted, [`track`] gives no statistics otherwise
+pub const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+/// Runs `f`, along with the heap use of the current thread while it ran when the allocations are
+/// counted
+#[cfg(feature = "alloc-stats")]
+pub fn track<O>(f: impl FnOnce() -> O) -> (O, Option<AllocStats>) {
+    use counting::COUNTERS;
+
+    let start = COUNTERS.with(|counters| {
+        let start = counters.get();
+        counters.set(counting::Counters {
+            peak: start.current,
+            ..start
+        });
+        start
+    });
+    let out = f();
+    let end = COUNTERS.with(|counters| {
+        let end = counters.get();
+        // Keeps the peak of an enclosing call
+        counters.set(counting::Counters {
+            peak: end.peak.max(start.peak),
+            ..end
+        });
+        end
+    });
+
+    let stats = AllocStats {
+        peak: (end.peak - start.current).max(0) as usize,
+        total: end.total - start.total,
+        count: end.count - start.count,
+    };
+    (out, Some(stats))
+}
+
+/// Runs `f`, along with the heap use of the current thread while it ran when the allocations are
+/// counted
+#[cfg(not(feature = "alloc-stats"))]
+pub fn track<O>(f: impl FnOnce() -> O) -> (O, Option<AllocStats>) {
+    (f(), None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{track, AllocStats, Bytes, ENABLED};
+
+    #[test]
+    fn bytes() {
+        assert_eq!(Bytes(512).to_string(), "512 B");
+        assert_eq!(Bytes(1536).to_string(), "1.5 KiB");
+        assert_eq!(Bytes(3 * 1024 * 1024).to_string(), "3.0 MiB");
+    }
+
+    #[test]
+    fn tracking() {
+        let (len, stats) = track(|| {
+            let kept: Vec<u8> = Vec::with_capacity(4096);
+            for _ in 0..3 {
+                drop(Vec::<u8>::with_capacity(1024));
+            }
+            kept.capacity()
+        });
+        assert_eq!(len, 4096);
+
+        assert_eq!(stats.is_some(), ENABLED);
+        if let Some(AllocStats { peak, total, count }) = stats {
+            assert!((5120..8192).contains(&peak), "peak was {}", peak);
+            assert!(total >= 4096 + 3 * 1024);
+            assert!(count >= 4);
+        }
+    }
+}
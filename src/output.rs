@@ -0,0 +1,405 @@
+use std::{str::FromStr, time::Duration};
+
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use aoc::memory::{AllocStats, Bytes};
+
+use crate::{
+    answers::Verdict,
+    harness::{render_table, Align, Outcome},
+    EyreResult, Part,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => eyre::bail!("Unknown output format: {}", other),
+        }
+    }
+}
+
+pub(crate) struct SummaryRow {
+    pub(crate) year: usize,
+    pub(crate) day: usize,
+    pub(crate) part: Part,
+    /// The input the day ran on, when running on several inputs
+    pub(crate) input: Option<String>,
+    pub(crate) outcome: EyreResult<Outcome>,
+    pub(crate) verdict: Option<Verdict>,
+}
+
+fn summary_answer(outcome: &EyreResult<Outcome>) -> String {
+    match outcome {
+        Ok(outcome) if outcome.answer.trim().contains('\n') => {
+            format!("<{} lines>", outcome.answer.trim().lines().count())
+        }
+        Ok(outcome) => outcome.answer.clone(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+/// Renders an aligned table of the answers and timings of several runs
+pub(crate) fn render_summary(rows: &[SummaryRow]) -> String {
+    let checked = rows.iter().any(|row| row.verdict.is_some());
+    let shared = rows.iter().any(|row| {
+        row.outcome
+            .as_ref()
+            .map(|outcome| outcome.timings.inter.is_some())
+            .unwrap_or(false)
+    });
+    let allocations = rows.iter().any(|row| {
+        row.outcome
+            .as_ref()
+            .map(|outcome| outcome.allocations.is_some())
+            .unwrap_or(false)
+    });
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            let (parse, inter, solve) = match &row.outcome {
+                Ok(outcome) => (
+                    match outcome.timings.parse {
+                        Some(parse) => format!("{:.2?}", parse),
+                        None => "reused".into(),
+                    },
+                    match outcome.timings.inter {
+                        Some(inter) => format!("{:.2?}", inter),
+                        None => "-".into(),
+                    },
+                    format!("{:.2?}", outcome.timings.part),
+                ),
+                Err(_) => ("-".into(), "-".into(), "-".into()),
+            };
+            let mut cells = vec![
+                row.day.to_string(),
+                row.part.to_string(),
+                summary_answer(&row.outcome),
+                parse,
+            ];
+            if shared {
+                cells.push(inter);
+            }
+            cells.push(solve);
+            if allocations {
+                let part = row
+                    .outcome
+                    .as_ref()
+                    .ok()
+                    .and_then(|outcome| outcome.allocations)
+                    .map(|allocations| allocations.part);
+                match part {
+                    Some(part) => {
+                        cells.push(Bytes(part.peak).to_string());
+                        cells.push(Bytes(part.total).to_string());
+                        cells.push(part.count.to_string());
+                    }
+                    None => cells.extend(["-".into(), "-".into(), "-".into()]),
+                }
+            }
+            if checked {
+                cells.push(match &row.verdict {
+                    Some(verdict) => verdict.to_string(),
+                    None => "-".into(),
+                });
+            }
+            cells
+        })
+        .collect();
+
+    let mut header = vec![
+        ("Day", Align::Right),
+        ("Part", Align::Right),
+        ("Answer", Align::Left),
+        ("Parse", Align::Right),
+    ];
+    if shared {
+        header.push(("Shared", Align::Right));
+    }
+    header.push(("Solve", Align::Right));
+    if allocations {
+        header.push(("Peak", Align::Right));
+        header.push(("Allocated", Align::Right));
+        header.push(("Allocs", Align::Right));
+    }
+    if checked {
+        header.push(("Check", Align::Left));
+    }
+
+    let mut out = render_table(&header, &cells);
+
+    let successes = rows.iter().filter_map(|row| row.outcome.as_ref().ok());
+    let (parse, solve) = successes.fold(
+        (Duration::ZERO, Duration::ZERO),
+        |(parse, solve), outcome| {
+            (
+                parse + outcome.timings.parse.unwrap_or_default(),
+                solve + outcome.timings.inter.unwrap_or_default() + outcome.timings.part,
+            )
+        },
+    );
+    let failures = rows.iter().filter(|row| row.outcome.is_err()).count();
+    out += &format!(
+        "\nTotal: {:.2?} (parsing {:.2?}, solving {:.2?}), {} run(s), {} failure(s)",
+        parse + solve,
+        parse,
+        solve,
+        rows.len(),
+        failures,
+    );
+    if checked {
+        let count = |pred: fn(&Verdict) -> bool| {
+            rows.iter()
+                .filter(|row| row.verdict.as_ref().map(pred).unwrap_or(false))
+                .count()
+        };
+        out += &format!(
+            "\nCheck: {} passed, {} failed, {} missing",
+            count(|v| *v == Verdict::Pass),
+            count(|v| matches!(v, Verdict::Fail { .. })),
+            count(|v| *v == Verdict::Missing),
+        );
+    }
+    out
+}
+
+/// Renders the answers of each part as rows and the inputs as columns, `columns` holding the rows of
+/// each input in the order they were given. The same input can be given several times.
+pub(crate) fn render_side_by_side(columns: &[Vec<SummaryRow>]) -> String {
+    let mut parts: Vec<Part> = Vec::new();
+    for row in columns.iter().flatten() {
+        if !parts.contains(&row.part) {
+            parts.push(row.part);
+        }
+    }
+
+    let cells: Vec<Vec<String>> = parts
+        .iter()
+        .map(|&part| {
+            let mut cells = vec![part.to_string()];
+            cells.extend(columns.iter().map(|rows| {
+                rows.iter()
+                    .find(|row| row.part == part)
+                    .map(|row| summary_answer(&row.outcome))
+                    .unwrap_or_else(|| "-".into())
+            }));
+            cells
+        })
+        .collect();
+
+    let mut header = vec![("Part", Align::Right)];
+    header.extend(columns.iter().map(|rows| {
+        let input = rows.first().and_then(|row| row.input.as_deref());
+        (input.unwrap_or_default(), Align::Left)
+    }));
+    render_table(&header, &cells)
+}
+
+/// A run in a form suitable for scripts, durations are in nanoseconds and memory in bytes. Every
+/// field is present in both JSON and CSV, left empty when it does not apply, such as the heap use
+/// when the allocations are not counted
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct Record {
+    pub(crate) year: usize,
+    pub(crate) day: usize,
+    pub(crate) part: String,
+    pub(crate) input: Option<String>,
+    pub(crate) answer: Option<String>,
+    pub(crate) parse_ns: Option<u128>,
+    pub(crate) inter_ns: Option<u128>,
+    pub(crate) solve_ns: Option<u128>,
+    pub(crate) error: Option<String>,
+    pub(crate) check: Option<String>,
+    pub(crate) expected: Option<String>,
+    pub(crate) parse_alloc: Option<AllocStats>,
+    pub(crate) solve_alloc: Option<AllocStats>,
+}
+
+impl From<&SummaryRow> for Record {
+    fn from(row: &SummaryRow) -> Self {
+        let (answer, timings, allocations, error) = match &row.outcome {
+            Ok(outcome) => (
+                Some(outcome.answer.clone()),
+                Some(outcome.timings),
+                outcome.allocations,
+                None,
+            ),
+            Err(e) => (None, None, None, Some(format!("{:#}", e))),
+        };
+        Record {
+            year: row.year,
+            day: row.day,
+            part: row.part.to_string(),
+            input: row.input.clone(),
+            answer,
+            parse_ns: timings.and_then(|t| t.parse).map(|d| d.as_nanos()),
+            inter_ns: timings.and_then(|t| t.inter).map(|d| d.as_nanos()),
+            solve_ns: timings.map(|t| t.part.as_nanos()),
+            error,
+            check: row.verdict.as_ref().map(ToString::to_string),
+            expected: match &row.verdict {
+                Some(Verdict::Fail { expected }) => Some(expected.clone()),
+                _ => None,
+            },
+            parse_alloc: allocations.and_then(|a| a.parse),
+            solve_alloc: allocations.map(|a| a.part),
+        }
+    }
+}
+
+pub(crate) fn render_json(rows: &[SummaryRow]) -> EyreResult<String> {
+    let records: Vec<Record> = rows.iter().map(Into::into).collect();
+    Ok(serde_json::to_string_pretty(&records)?)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub(crate) fn render_csv(rows: &[SummaryRow]) -> String {
+    let mut out = String::from(
+        "year,day,part,answer,parse_ns,inter_ns,solve_ns,error,check,expected,input,\
+         parse_peak_bytes,parse_alloc_bytes,parse_allocs,solve_peak_bytes,solve_alloc_bytes,\
+         solve_allocs\n",
+    );
+    for row in rows {
+        let record = Record::from(row);
+        let opt = |v: Option<u128>| v.map(|v| v.to_string()).unwrap_or_default();
+        let alloc = |stats: Option<AllocStats>| {
+            let field = |f: fn(AllocStats) -> usize| opt(stats.map(|s| f(s) as u128));
+            [field(|s| s.peak), field(|s| s.total), field(|s| s.count)]
+        };
+        let [parse_peak, parse_total, parse_count] = alloc(record.parse_alloc);
+        let [solve_peak, solve_total, solve_count] = alloc(record.solve_alloc);
+        let fields = [
+            record.year.to_string(),
+            record.day.to_string(),
+            record.part,
+            record.answer.unwrap_or_default(),
+            opt(record.parse_ns),
+            opt(record.inter_ns),
+            opt(record.solve_ns),
+            record.error.unwrap_or_default(),
+            record.check.unwrap_or_default(),
+            record.expected.unwrap_or_default(),
+            record.input.unwrap_or_default(),
+            parse_peak,
+            parse_total,
+            parse_count,
+            solve_peak,
+            solve_total,
+            solve_count,
+        ];
+        out += &fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{csv_field, render_csv, render_json, render_side_by_side, SummaryRow};
+    use crate::{
+        harness::{Outcome, Timings},
+        Part,
+    };
+
+    fn row(part: Part, input: &str, answer: &str) -> SummaryRow {
+        SummaryRow {
+            year: 2021,
+            day: 1,
+            part,
+            input: Some(input.into()),
+            outcome: Ok(Outcome {
+                answer: answer.into(),
+                message: answer.into(),
+                timings: Timings {
+                    parse: None,
+                    inter: None,
+                    part: Default::default(),
+                },
+                allocations: None,
+                picture: None,
+            }),
+            verdict: None,
+        }
+    }
+
+    #[test]
+    fn csv_escaping() {
+        assert_eq!(csv_field("1234"), "1234");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("# #\n##"), "\"# #\n##\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn side_by_side() {
+        let columns = [
+            vec![
+                row(Part::Part1, "alice", "7"),
+                row(Part::Part2, "alice", "1234"),
+            ],
+            vec![row(Part::Part1, "bob", "12")],
+        ];
+        assert_eq!(
+            render_side_by_side(&columns),
+            "Part | alice | bob\n\
+             -----+-------+----\n   \
+             1 | 7     | 12\n   \
+             2 | 1234  | -"
+        );
+
+        // An input given twice keeps a column for each run
+        let columns = [
+            vec![row(Part::Part1, "alice", "7")],
+            vec![row(Part::Part1, "alice", "8")],
+        ];
+        assert_eq!(
+            render_side_by_side(&columns),
+            "Part | alice | alice\n\
+             -----+-------+------\n   \
+             1 | 7     | 8"
+        );
+    }
+
+    #[test]
+    fn without_allocations() {
+        let rows = [row(Part::Part1, "alice", "7")];
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&rows).unwrap()).unwrap();
+        assert_eq!(json[0]["parse_alloc"], serde_json::Value::Null);
+        assert_eq!(json[0]["solve_alloc"], serde_json::Value::Null);
+
+        let csv = render_csv(&rows);
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert!(header.ends_with("solve_allocs"));
+        assert_eq!(
+            lines.next().unwrap().split(',').count(),
+            header.split(',').count()
+        );
+    }
+}
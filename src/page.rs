@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+use ansi_term::Style;
+use color_eyre::eyre::Context;
+
+use aoc::examples::{self, Expected};
+
+use crate::{download, EyreResult, Part};
+
+enum Token<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Splits HTML in tags and text, comments and declarations are dropped
+fn tokens(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .split_once("-->")
+                .map(|(_, r)| r)
+                .unwrap_or_default();
+        } else if let Some(tag) = rest.strip_prefix('<') {
+            let (tag, r) = tag.split_once('>').unwrap_or((tag, ""));
+            rest = r;
+            let name = tag
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default();
+            if tag.starts_with('/') {
+                tokens.push(Token::Close(name));
+            } else if !tag.starts_with('!') {
+                tokens.push(Token::Open(name));
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Example block of a puzzle, with the answers suggested by the text that follows it
+pub(crate) struct Example {
+    pub(crate) content: String,
+    pub(crate) answers: [Option<String>; 2],
+}
+
+pub(crate) struct Puzzle {
+    /// Name of the puzzle, without the day
+    pub(crate) title: Option<String>,
+    /// The puzzle description, formatted for the terminal
+    pub(crate) text: String,
+    pub(crate) examples: Vec<Example>,
+}
+
+/// Extracts the puzzle of a page, each part is in an `<article>`
+///
+/// The answers of the examples are guessed from the emphasized code (`<code><em>`) following the
+/// example, the last one before the next example is kept.
+pub(crate) fn parse(html: &str, color: bool) -> Puzzle {
+    let style = |style: Style, text: &str| {
+        if color {
+            style.paint(text).to_string()
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut text = String::new();
+    let mut examples: Vec<Example> = Vec::new();
+    let mut part = Part::Part1;
+    let mut articles = 0;
+    let mut in_article = false;
+    let (mut in_pre, mut in_code, mut in_em, mut in_title) = (false, false, false, false);
+    let mut block = String::new();
+    let mut heading = String::new();
+
+    for token in tokens(html) {
+        match (token, in_article) {
+            (Token::Open("article"), _) => {
+                in_article = true;
+                part = if articles == 0 {
+                    Part::Part1
+                } else {
+                    Part::Part2
+                };
+                articles += 1;
+            }
+            (Token::Close("article"), _) => {
+                in_article = false;
+                text += "\n";
+            }
+            (_, false) => (),
+            (Token::Open("h2"), _) => in_title = true,
+            (Token::Close("h2"), _) => {
+                in_title = false;
+                text += "\n\n";
+            }
+            (Token::Close("p"), _) => text += "\n\n",
+            (Token::Open("li"), _) => text += "  - ",
+            (Token::Close("li"), _) => text.push('\n'),
+            (Token::Close("ul"), _) => text.push('\n'),
+            (Token::Open("pre"), _) => in_pre = true,
+            (Token::Close("pre"), _) => {
+                in_pre = false;
+                for line in block.lines() {
+                    text += &format!("    {}\n", style(Style::new().dimmed(), line));
+                }
+                text.push('\n');
+                examples.push(Example {
+                    content: std::mem::take(&mut block),
+                    answers: [None, None],
+                });
+            }
+            (Token::Open("code"), _) => in_code = true,
+            (Token::Close("code"), _) => in_code = false,
+            (Token::Open("em"), _) => in_em = true,
+            (Token::Close("em"), _) => in_em = false,
+            (Token::Text(raw), true) => {
+                let decoded = decode_entities(raw);
+                if in_pre {
+                    block += &decoded;
+                    continue;
+                }
+                if in_title && articles == 1 {
+                    heading += &decoded;
+                }
+
+                let collapsed = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+                let mut chunk = String::new();
+                if decoded.starts_with(char::is_whitespace) && !text.ends_with([' ', '\n']) {
+                    chunk.push(' ');
+                }
+                chunk += &collapsed;
+                if decoded.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+                    chunk.push(' ');
+                }
+
+                if in_em && in_code {
+                    if let Some(example) = examples.last_mut() {
+                        let index = match part {
+                            Part::Part1 => 0,
+                            Part::Part2 => 1,
+                        };
+                        example.answers[index] = Some(collapsed.clone());
+                    }
+                }
+                text += &match (in_title, in_em) {
+                    (true, _) => style(Style::new().bold().underline(), &chunk),
+                    (_, true) => style(Style::new().bold(), &chunk),
+                    _ => chunk,
+                };
+            }
+            (Token::Open(_) | Token::Close(_), _) => (),
+        }
+    }
+
+    // The heading reads `--- Day 1: Sonar Sweep ---`
+    let title = heading
+        .trim()
+        .trim_matches('-')
+        .split_once(':')
+        .map(|(_, title)| title.trim().to_string());
+    Puzzle {
+        title,
+        text: text.trim_end().to_string(),
+        examples,
+    }
+}
+
+pub(crate) fn example_path(dir: &Path, day: usize, index: usize) -> PathBuf {
+    dir.join(format!("day{}-{}", day, index + 1))
+}
+
+/// Writes the examples as `<dir>/day<N>-<K>` along with their suggested answers, existing files are
+/// kept. Gives the written paths
+pub(crate) fn write_examples(
+    dir: &Path,
+    day: usize,
+    examples: &[Example],
+) -> EyreResult<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for (index, example) in examples.iter().enumerate() {
+        let path = example_path(dir, day, index);
+        if path.exists() {
+            eprintln!("{:?} already exists, it was not replaced", path);
+            continue;
+        }
+        download::write_atomic(&path, example.content.as_bytes())
+            .with_context(|| format!("Could not write the example {}", index + 1))?;
+
+        let [part1, part2] = example.answers.clone();
+        let expected = Expected { part1, part2 };
+        if expected != Expected::default() {
+            let content = format!("# Suggested from the puzzle page\n{}", expected.render());
+            download::write_atomic(&examples::sidecar_path(&path), content.as_bytes())?;
+        }
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Day 1</title></head><body>
+<main>
+<article class="day-desc"><h2>--- Day 1: Sonar Sweep ---</h2>
+<p>For example, suppose you had the following report:</p>
+<pre><code>199
+200
+<em>208</em>
+</code></pre>
+<!-- comment -->
+<p>In this example, there are <code><em>7</em></code> measurements that are larger &amp; deeper.</p>
+</article>
+<p>Your puzzle answer was <code>1000</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<ul><li>Use a <em>window</em> of <code>3</code></li></ul>
+<p>In the above example, there are <code><em>5</em></code> sums.</p>
+</article>
+</main></body></html>"#;
+
+    #[test]
+    fn puzzle_page() {
+        let puzzle = parse(PAGE, false);
+        assert_eq!(puzzle.title.as_deref(), Some("Sonar Sweep"));
+        assert_eq!(
+            puzzle.text,
+            "--- Day 1: Sonar Sweep ---\n\n\
+             For example, suppose you had the following report:\n\n    \
+             199\n    200\n    208\n\n\
+             In this example, there are 7 measurements that are larger & deeper.\n\n\n\
+             --- Part Two ---\n\n  \
+             - Use a window of 3\n\n\
+             In the above example, there are 5 sums."
+        );
+        assert_eq!(puzzle.examples.len(), 1);
+        assert_eq!(puzzle.examples[0].content, "199\n200\n208\n");
+        assert_eq!(
+            puzzle.examples[0].answers,
+            [Some("7".into()), Some("5".into())]
+        );
+    }
+}
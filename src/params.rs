@@ -0,0 +1,161 @@
+//! Tunable constants of the solutions
+//!
+//! A solution declares its [`Param`]s in [`Solution::PARAMS`](crate::solution::Solution::PARAMS)
+//! and reads them with [`Param::get`], which gives the value set in the current [`scope`] or the
+//! default of the part.
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Display, str::FromStr};
+
+use color_eyre::eyre::{self, Context};
+
+use crate::{EyreResult, Part};
+
+/// Values given to the parameters, by name
+pub type Overrides = BTreeMap<String, String>;
+
+thread_local! {
+    static CURRENT: RefCell<Overrides> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Runs `f` with the parameters of the current thread set to `overrides`
+pub fn scope<O>(overrides: &Overrides, f: impl FnOnce() -> O) -> O {
+    let previous = CURRENT.with(|current| current.replace(overrides.clone()));
+    let out = f();
+    CURRENT.with(|current| current.replace(previous));
+    out
+}
+
+/// Parses a `name=value` override
+pub fn parse_override(s: &str) -> EyreResult<(String, String)> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => eyre::bail!("Parameters are given as name=value, got {:?}", s),
+    }
+}
+
+/// A named constant of a solution, which may have a different default for each part
+pub struct Param<T: 'static> {
+    name: &'static str,
+    help: &'static str,
+    defaults: [T; 2],
+    same: bool,
+}
+
+impl<T: Copy> Param<T> {
+    pub const fn new(name: &'static str, help: &'static str, default: T) -> Self {
+        Self {
+            name,
+            help,
+            defaults: [default, default],
+            same: true,
+        }
+    }
+
+    pub const fn per_part(name: &'static str, help: &'static str, part1: T, part2: T) -> Self {
+        Self {
+            name,
+            help,
+            defaults: [part1, part2],
+            same: false,
+        }
+    }
+
+    fn default(&self, part: Part) -> T {
+        match part {
+            Part::Part1 => self.defaults[0],
+            Part::Part2 => self.defaults[1],
+        }
+    }
+}
+
+impl<T> Param<T>
+where
+    T: Copy + FromStr,
+    T::Err: Display,
+{
+    fn parse(&self, value: &str) -> EyreResult<T> {
+        value
+            .parse()
+            .map_err(|e| eyre::eyre!("{}", e))
+            .with_context(|| format!("Invalid value for the parameter {}: {:?}", self.name, value))
+    }
+
+    /// Value of the parameter for `part`
+    pub fn get(&self, part: Part) -> EyreResult<T> {
+        match CURRENT.with(|current| current.borrow().get(self.name).cloned()) {
+            Some(value) => self.parse(&value),
+            None => Ok(self.default(part)),
+        }
+    }
+}
+
+/// A [`Param`] of any type, as listed by a solution
+pub trait AnyParam: Sync {
+    fn name(&self) -> &'static str;
+    fn help(&self) -> &'static str;
+    /// The default value, or the one of each part separated by a `/`
+    fn defaults(&self) -> String;
+    /// Value of the parameter for `part`, as displayed
+    fn value(&self, part: Part) -> EyreResult<String>;
+    /// Checks that `value` can be given to the parameter
+    fn check(&self, value: &str) -> EyreResult<()>;
+}
+
+impl<T> AnyParam for Param<T>
+where
+    T: Copy + FromStr + Display + Sync,
+    T::Err: Display,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn help(&self) -> &'static str {
+        self.help
+    }
+
+    fn defaults(&self) -> String {
+        if self.same {
+            self.defaults[0].to_string()
+        } else {
+            format!("{}/{}", self.defaults[0], self.defaults[1])
+        }
+    }
+
+    fn value(&self, part: Part) -> EyreResult<String> {
+        self.get(part).map(|value| value.to_string())
+    }
+
+    fn check(&self, value: &str) -> EyreResult<()> {
+        self.parse(value).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_override, scope, AnyParam, Overrides, Param};
+    use crate::Part;
+
+    const STEPS: Param<usize> = Param::per_part("steps", "Number of steps", 10, 40);
+
+    #[test]
+    fn overrides() {
+        assert_eq!(STEPS.get(Part::Part1).unwrap(), 10);
+        assert_eq!(STEPS.get(Part::Part2).unwrap(), 40);
+        assert_eq!(STEPS.defaults(), "10/40");
+
+        let overrides: Overrides = [parse_override("steps = 3").unwrap()].into();
+        scope(&overrides, || {
+            assert_eq!(STEPS.get(Part::Part1).unwrap(), 3);
+            assert_eq!(STEPS.get(Part::Part2).unwrap(), 3);
+        });
+        assert_eq!(STEPS.get(Part::Part1).unwrap(), 10);
+
+        let overrides: Overrides = [parse_override("steps=-1").unwrap()].into();
+        assert!(scope(&overrides, || STEPS.get(Part::Part1)).is_err());
+        assert!(STEPS.check("-1").is_err());
+        assert!(parse_override("steps").is_err());
+    }
+}
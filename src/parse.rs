@@ -0,0 +1,147 @@
+//! Errors pointing at the part of a puzzle input that could not be parsed
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::utils::split2;
+
+/// Error in a puzzle input, displayed with the offending line and a caret under the offending text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// Line of the error, starting at 1
+    pub line: usize,
+    /// Column of the error in characters, starting at 1
+    pub column: usize,
+    /// The line of the input containing the error
+    pub text: String,
+    /// Length in characters of the offending text, at least 1
+    pub len: usize,
+}
+
+impl ParseError {
+    /// Error on `at`, which must be a slice of `input`. When it is not, the error points at the end
+    /// of the input
+    pub fn new(input: &str, at: &str, message: impl Display) -> Self {
+        let offset = (at.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|&offset| offset + at.len() <= input.len())
+            .unwrap_or(input.len());
+
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Self {
+            message: message.to_string(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            text: input[line_start..]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            len: at.lines().next().unwrap_or_default().chars().count().max(1),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = self.line.to_string().len();
+        writeln!(
+            f,
+            "{} (line {}, column {})",
+            self.message, self.line, self.column
+        )?;
+        writeln!(f, "{:gutter$} |", "", gutter = gutter)?;
+        writeln!(f, "{} | {}", self.line, self.text)?;
+        write!(
+            f,
+            "{:gutter$} | {:indent$}{}",
+            "",
+            "",
+            "^".repeat(self.len),
+            gutter = gutter,
+            indent = self.column - 1
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `token`, a slice of `input`, with its [`FromStr`] implementation
+pub fn token<T>(input: &str, token: &str) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    token
+        .parse()
+        .map_err(|e| ParseError::new(input, token, format!("Invalid value {:?}: {}", token, e)))
+}
+
+/// Splits `text`, a slice of `input`, around the first occurrence of `pattern`
+pub fn split<'t>(
+    input: &str,
+    text: &'t str,
+    pattern: &str,
+) -> Result<(&'t str, &'t str), ParseError> {
+    split2(text, pattern)
+        .ok_or_else(|| ParseError::new(input, text, format!("Expected {:?}", pattern)))
+}
+
+/// Grid of digits, such as a height map, as its width and its digits line by line
+pub fn digit_grid(input: &str) -> Result<(usize, Vec<u8>), ParseError> {
+    let width = input.lines().next().unwrap_or_default().trim_end().len();
+    if width == 0 {
+        return Err(ParseError::new(input, input, "The grid is empty"));
+    }
+
+    let mut digits = Vec::new();
+    for line in input.lines() {
+        let line = line.trim_end();
+        for (i, c) in line.char_indices() {
+            let digit = c.to_digit(10).ok_or_else(|| {
+                ParseError::new(input, &line[i..i + c.len_utf8()], "Expected a digit")
+            })?;
+            digits.push(digit as u8);
+        }
+        if line.len() != width {
+            return Err(ParseError::new(
+                input,
+                line,
+                format!("Expected {} digits like the first line", width),
+            ));
+        }
+    }
+    Ok((width, digits))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{digit_grid, split, token, ParseError};
+
+    #[test]
+    fn position() {
+        let input = "1,2\n3,x\n5,6";
+        let line = input.lines().nth(1).unwrap();
+        let (_, y) = split(input, line, ",").unwrap();
+        let err = token::<u64>(input, y).unwrap_err();
+        assert_eq!((err.line, err.column, err.len), (2, 3, 1));
+        assert_eq!(err.text, "3,x");
+        assert_eq!(
+            err.to_string(),
+            "Invalid value \"x\": invalid digit found in string (line 2, column 3)\n  |\n2 | 3,x\n  |   ^"
+        );
+
+        let err = split(input, input.lines().last().unwrap(), "->").unwrap_err();
+        assert_eq!((err.line, err.column, err.len), (3, 1, 3));
+
+        let err = ParseError::new(input, "elsewhere", "Missing line");
+        assert_eq!((err.line, err.column), (3, 4));
+
+        assert_eq!(digit_grid("12\n34\n").unwrap(), (2, vec![1, 2, 3, 4]));
+        let err = digit_grid("12\n345").unwrap_err();
+        assert_eq!((err.line, err.column, err.len), (2, 1, 3));
+        let err = digit_grid("12\n3a").unwrap_err();
+        assert_eq!((err.line, err.column, err.len), (2, 2, 1));
+    }
+}
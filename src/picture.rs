@@ -0,0 +1,459 @@
+//! Pictures of the state of the solutions, saved as image files
+//!
+//! The solutions draw their state with [`draw`], which does nothing outside of a [`scope`] that
+//! enables the pictures. The last picture drawn by a part is [`capture`]d in its outcome, and
+//! encoded as PPM, PNG or SVG by [`Format::encode`].
+
+use std::{cell::RefCell, fmt::Write, path::Path, str::FromStr};
+
+use color_eyre::eyre;
+
+use crate::EyreResult;
+
+pub type Rgb = [u8; 3];
+
+/// What a cell shows, the [`Palette`] decides its colour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint {
+    Empty,
+    /// Intensity between 0 and 1, such as a height or a number of overlaps
+    Level(f32),
+    /// One of several groups, such as the basins of a map
+    Group(usize),
+    /// Stands out from the rest, such as a path
+    Highlight,
+}
+
+/// A segment between the centers of two cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub paint: Paint,
+}
+
+/// A grid of cells, with optional lines drawing the same thing for vector formats
+#[derive(Debug, Clone, PartialEq)]
+pub struct Picture {
+    width: usize,
+    height: usize,
+    cells: Vec<Paint>,
+    lines: Vec<Line>,
+}
+
+impl Picture {
+    /// An empty picture
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Paint::Empty; width * height],
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Paint {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, paint: Paint) {
+        self.cells[y * self.width + x] = paint;
+    }
+
+    /// Adds a line, only drawn by SVG in place of the cells
+    pub fn line(&mut self, from: (usize, usize), to: (usize, usize), paint: Paint) {
+        self.lines.push(Line { from, to, paint });
+    }
+}
+
+thread_local! {
+    static ENABLED: RefCell<bool> = const { RefCell::new(false) };
+    static DRAWN: RefCell<Option<Picture>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the pictures drawn when `enabled` is set
+pub fn scope<O>(enabled: bool, f: impl FnOnce() -> O) -> O {
+    let previous = ENABLED.with(|current| current.replace(enabled));
+    let out = f();
+    ENABLED.with(|current| current.replace(previous));
+    out
+}
+
+/// Whether the pictures are drawn, to skip the work only needed by them
+pub fn enabled() -> bool {
+    ENABLED.with(|enabled| *enabled.borrow())
+}
+
+/// Keeps the picture built by `picture`, replacing the one drawn before
+pub fn draw(picture: impl FnOnce() -> Picture) {
+    if enabled() {
+        let picture = picture();
+        DRAWN.with(|drawn| drawn.replace(Some(picture)));
+    }
+}
+
+/// Runs `f`, giving the last picture it drew
+pub fn capture<O>(f: impl FnOnce() -> O) -> (O, Option<Picture>) {
+    let previous = DRAWN.with(|drawn| drawn.take());
+    let out = f();
+    let picture = DRAWN.with(|drawn| drawn.replace(previous));
+    (out, picture)
+}
+
+/// Colours given to the paints of a picture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    background: Rgb,
+    /// Colours of the levels, from 0 to 1
+    gradient: &'static [Rgb],
+    groups: &'static [Rgb],
+    highlight: Rgb,
+}
+
+const GROUPS: &[Rgb] = &[
+    [228, 26, 28],
+    [55, 126, 184],
+    [77, 175, 74],
+    [152, 78, 163],
+    [255, 127, 0],
+    [255, 255, 51],
+    [166, 86, 40],
+    [247, 129, 191],
+    [102, 194, 165],
+    [141, 160, 203],
+    [166, 216, 84],
+    [229, 196, 148],
+];
+
+impl Palette {
+    pub const NAMES: &'static [&'static str] = &["viridis", "heat", "gray"];
+
+    pub const VIRIDIS: Palette = Palette {
+        background: [16, 16, 24],
+        gradient: &[
+            [68, 1, 84],
+            [59, 82, 139],
+            [33, 145, 140],
+            [94, 201, 98],
+            [253, 231, 37],
+        ],
+        groups: GROUPS,
+        highlight: [255, 64, 64],
+    };
+
+    pub const HEAT: Palette = Palette {
+        background: [0, 0, 0],
+        gradient: &[[96, 0, 0], [224, 32, 0], [255, 160, 0], [255, 255, 192]],
+        groups: GROUPS,
+        highlight: [64, 160, 255],
+    };
+
+    /// Dark cells on white, for printing
+    pub const GRAY: Palette = Palette {
+        background: [255, 255, 255],
+        gradient: &[[208, 208, 208], [0, 0, 0]],
+        groups: GROUPS,
+        highlight: [220, 0, 0],
+    };
+
+    pub fn colour(&self, paint: Paint) -> Rgb {
+        match paint {
+            Paint::Empty => self.background,
+            Paint::Level(level) => {
+                let at = level.clamp(0., 1.) * (self.gradient.len() - 1) as f32;
+                let low = (at as usize).min(self.gradient.len() - 2);
+                let t = at - low as f32;
+                let (a, b) = (self.gradient[low], self.gradient[low + 1]);
+                [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)
+            }
+            Paint::Group(group) => self.groups[group % self.groups.len()],
+            Paint::Highlight => self.highlight,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::VIRIDIS
+    }
+}
+
+impl FromStr for Palette {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viridis" => Ok(Self::VIRIDIS),
+            "heat" => Ok(Self::HEAT),
+            "gray" => Ok(Self::GRAY),
+            _ => eyre::bail!("Unknown palette {:?}, use {}", s, Self::NAMES.join(", ")),
+        }
+    }
+}
+
+/// File format of an exported picture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ppm,
+    Png,
+    Svg,
+}
+
+impl Format {
+    /// The format matching the extension of `path`
+    pub fn from_path(path: &Path) -> EyreResult<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("ppm") => Ok(Format::Ppm),
+            Some("png") => Ok(Format::Png),
+            Some("svg") => Ok(Format::Svg),
+            _ => eyre::bail!(
+                "Pictures are exported as .ppm, .png or .svg, got {:?}",
+                path
+            ),
+        }
+    }
+
+    /// The file content of `picture`, with each cell `scale` pixels wide
+    pub fn encode(self, picture: &Picture, palette: &Palette, scale: usize) -> Vec<u8> {
+        match self {
+            Format::Ppm => Image::new(picture, palette, scale).ppm(),
+            Format::Png => Image::new(picture, palette, scale).png(),
+            Format::Svg => svg(picture, palette, scale).into_bytes(),
+        }
+    }
+}
+
+/// Pixels of a picture, row by row
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn new(picture: &Picture, palette: &Palette, scale: usize) -> Self {
+        let (width, height) = (picture.width * scale, picture.height * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(palette.colour(picture.get(x / scale, y / scale)));
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(self.pixels.iter().flatten());
+        out
+    }
+
+    fn png(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits RGB, default compression and filtering, not interlaced
+        header.extend([8, 2, 0, 0, 0]);
+
+        // Each row starts with its filter, none is used
+        let mut raw = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut out, b"IHDR", &header);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// A zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut n = 0;
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xedb88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[n] = c;
+            n += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (a, b) = data.iter().fold((1, 0), |(a, b), &byte| {
+        let a = (a + byte as u32) % MOD;
+        (a, (b + a) % MOD)
+    });
+    (b << 16) | a
+}
+
+fn hex(colour: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+}
+
+/// The picture as SVG: its lines when it has some, else the runs of equal cells of each row
+fn svg(picture: &Picture, palette: &Palette, scale: usize) -> String {
+    let (width, height) = (picture.width, picture.height);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\n",
+        width * scale,
+        height * scale,
+        width,
+        height
+    );
+    // Writing to a String can't fail
+    let _ = writeln!(
+        out,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width,
+        height,
+        hex(palette.background)
+    );
+
+    if !picture.lines.is_empty() {
+        // The lines are translucent, so that the overlaps stand out
+        out += "<g stroke-width=\"1\" stroke-linecap=\"square\" stroke-opacity=\"0.5\">\n";
+        for line in &picture.lines {
+            let _ = writeln!(
+                out,
+                "<line x1=\"{}.5\" y1=\"{}.5\" x2=\"{}.5\" y2=\"{}.5\" stroke=\"{}\"/>",
+                line.from.0,
+                line.from.1,
+                line.to.0,
+                line.to.1,
+                hex(palette.colour(line.paint))
+            );
+        }
+        out += "</g>\n";
+    } else {
+        for (y, row) in picture.cells.chunks(width.max(1)).enumerate() {
+            let mut x = 0;
+            for run in row.chunk_by(|a, b| a == b) {
+                if run[0] != Paint::Empty {
+                    let _ = writeln!(
+                        out,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"{}\"/>",
+                        x,
+                        y,
+                        run.len(),
+                        hex(palette.colour(run[0]))
+                    );
+                }
+                x += run.len();
+            }
+        }
+    }
+    out += "</svg>\n";
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{adler32, capture, crc32, draw, scope, Format, Paint, Palette, Picture};
+
+    #[test]
+    fn encode() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+
+        let mut picture = Picture::new(2, 1);
+        picture.set(1, 0, Paint::Level(1.));
+        let palette = Palette::GRAY;
+        assert_eq!(palette.colour(Paint::Level(0.5)), [104, 104, 104]);
+
+        let ppm = Format::Ppm.encode(&picture, &palette, 2);
+        let mut expected = b"P6\n4 2\n255\n".to_vec();
+        for _ in 0..2 {
+            expected.extend([255; 6]);
+            expected.extend([0; 6]);
+        }
+        assert_eq!(ppm, expected);
+
+        let png = Format::Png.encode(&picture, &palette, 1);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        let svg = String::from_utf8(Format::Svg.encode(&picture, &palette, 3)).unwrap();
+        assert!(svg.contains("width=\"6\" height=\"3\" viewBox=\"0 0 2 1\""));
+        assert!(svg.contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#000000\"/>"));
+        picture.line((0, 0), (1, 0), Paint::Highlight);
+        let svg = String::from_utf8(Format::Svg.encode(&picture, &palette, 3)).unwrap();
+        assert!(
+            svg.contains("<line x1=\"0.5\" y1=\"0.5\" x2=\"1.5\" y2=\"0.5\" stroke=\"#dc0000\"/>")
+        );
+        assert!(!svg.contains("<rect x="));
+    }
+
+    #[test]
+    fn captured() {
+        let drawn = |n| {
+            draw(|| Picture::new(n, 1));
+            draw(|| Picture::new(n + 1, 1));
+        };
+        assert_eq!(capture(|| drawn(1)).1, None);
+        let ((), picture) = scope(true, || capture(|| drawn(1)));
+        assert_eq!(picture.map(|picture| picture.width()), Some(2));
+    }
+}
@@ -0,0 +1,196 @@
+use color_eyre::eyre;
+
+use aoc::solution::DEFAULT_YEAR;
+
+use crate::EyreResult;
+
+/// Fills the `{{DAY}}`, `{{TITLE}}` and `{{YEAR}}` placeholders of the template of a day, the lines
+/// with the year are removed for the default calendar
+pub(crate) fn render(template: &str, year: usize, day: usize, title: &str) -> String {
+    let title = title.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut out = String::new();
+    for line in template.lines() {
+        if year == DEFAULT_YEAR && line.contains("{{YEAR}}") {
+            continue;
+        }
+        out += &line
+            .replace("{{DAY}}", &day.to_string())
+            .replace("{{YEAR}}", &year.to_string())
+            .replace("{{TITLE}}", &title);
+        out.push('\n');
+    }
+    out
+}
+
+/// Module of the days of `year` from the root of the crate, the days of the default calendar are
+/// at the root
+pub(crate) fn year_module(year: usize) -> Option<String> {
+    (year != DEFAULT_YEAR).then(|| format!("y{}", year))
+}
+
+/// Name of a `pub mod day<N>;` or `pub mod y<YEAR>;` line
+fn module_name(line: &str) -> Option<&str> {
+    let name = line.strip_prefix("pub mod ")?.strip_suffix(';')?;
+    let number = name
+        .strip_prefix("day")
+        .or_else(|| name.strip_prefix('y'))?;
+    number.parse::<usize>().ok().map(|_| name)
+}
+
+/// Year and day of an `Entry::of::<day<N>::Day<N>>(),` or
+/// `Entry::of::<y<YEAR>::day<N>::Day<N>>(),` line of the registry
+fn entry_key(line: &str) -> Option<(usize, usize)> {
+    let path = line.trim().strip_prefix("Entry::of::<")?;
+    let (year, path) = match path.strip_prefix('y') {
+        Some(path) => {
+            let (year, path) = path.split_once("::")?;
+            (year.parse().ok()?, path)
+        }
+        None => (DEFAULT_YEAR, path),
+    };
+    let day = path.strip_prefix("day")?.split_once("::")?.0.parse().ok()?;
+    Some((year, day))
+}
+
+/// Adds a `pub mod <name>;` line among the modules of the days and years of `source`
+pub(crate) fn add_module(source: &str, name: &str) -> EyreResult<String> {
+    let mut lines: Vec<String> = source.lines().map(String::from).collect();
+
+    let modules: Vec<_> = (0..lines.len())
+        .filter(|&i| module_name(&lines[i]).is_some())
+        .collect();
+    let last = match modules.last() {
+        Some(&last) => last,
+        None => eyre::bail!("Could not find the modules of the days"),
+    };
+    if modules
+        .iter()
+        .any(|&i| module_name(&lines[i]) == Some(name))
+    {
+        eyre::bail!("The module {} is already registered", name);
+    }
+    // The modules are sorted by name, as rustfmt does
+    let at = modules
+        .iter()
+        .copied()
+        .find(|&i| module_name(&lines[i]).is_some_and(|module| module > name))
+        .unwrap_or(last + 1);
+    lines.insert(at, format!("pub mod {};", name));
+
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Adds the module of a day to `lib.rs` and its solution to the registry. The days of another
+/// calendar than the default one are in the `y<YEAR>` module, which is added when it is missing
+pub(crate) fn register(lib: &str, year: usize, day: usize) -> EyreResult<String> {
+    let name = format!("day{}", day);
+    let (lib, path) = match year_module(year) {
+        None => (add_module(lib, &name)?, name),
+        Some(module) => {
+            let registered = lib.lines().any(|line| module_name(line) == Some(&module));
+            let lib = if registered {
+                lib.to_string()
+            } else {
+                add_module(lib, &module)?
+            };
+            (lib, format!("{}::{}", module, name))
+        }
+    };
+    add_entry(&lib, year, day, &path)
+}
+
+/// Adds the solution of a day, whose module is at `path`, to the registry
+fn add_entry(lib: &str, year: usize, day: usize, path: &str) -> EyreResult<String> {
+    let mut lines: Vec<String> = lib.lines().map(String::from).collect();
+    let entries: Vec<_> = (0..lines.len())
+        .filter(|&i| entry_key(&lines[i]).is_some())
+        .collect();
+    let last = match entries.last() {
+        Some(&last) => last,
+        None => eyre::bail!("Could not find the registry"),
+    };
+    if entries
+        .iter()
+        .any(|&i| entry_key(&lines[i]) == Some((year, day)))
+    {
+        eyre::bail!("Day {} of {} is already registered", day, year);
+    }
+    let at = entries
+        .iter()
+        .copied()
+        .find(|&i| entry_key(&lines[i]) > Some((year, day)))
+        .unwrap_or(last + 1);
+    let indent: String = lines[last]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    lines.insert(
+        at,
+        format!("{}Entry::of::<{}::Day{}>(),", indent, path, day),
+    );
+
+    Ok(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_module, register, render};
+
+    #[test]
+    fn scaffold() {
+        let template = "pub struct Day{{DAY}};\nconst YEAR: usize = {{YEAR}};\nconst TITLE: &str = \"{{TITLE}}\";\n";
+        assert_eq!(
+            render(template, 2021, 24, "A \"quoted\" title"),
+            "pub struct Day24;\nconst TITLE: &str = \"A \\\"quoted\\\" title\";\n"
+        );
+        assert_eq!(
+            render(template, 2022, 1, "Calorie Counting"),
+            "pub struct Day1;\nconst YEAR: usize = 2022;\nconst TITLE: &str = \"Calorie Counting\";\n"
+        );
+
+        let lib = "pub mod utils;\n\npub mod day1;\npub mod day10;\npub mod day2;\n\n\
+                   fn registry() {\n    vec![\n        Entry::of::<day1::Day1>(),\n        \
+                   Entry::of::<day2::Day2>(),\n        Entry::of::<day10::Day10>(),\n    ]\n}\n";
+        assert_eq!(
+            register(lib, 2021, 3).unwrap(),
+            "pub mod utils;\n\npub mod day1;\npub mod day10;\npub mod day2;\npub mod day3;\n\n\
+             fn registry() {\n    vec![\n        Entry::of::<day1::Day1>(),\n        \
+             Entry::of::<day2::Day2>(),\n        Entry::of::<day3::Day3>(),\n        \
+             Entry::of::<day10::Day10>(),\n    ]\n}\n"
+        );
+        assert_eq!(
+            register(lib, 2021, 11).unwrap(),
+            "pub mod utils;\n\npub mod day1;\npub mod day10;\npub mod day11;\npub mod day2;\n\n\
+             fn registry() {\n    vec![\n        Entry::of::<day1::Day1>(),\n        \
+             Entry::of::<day2::Day2>(),\n        Entry::of::<day10::Day10>(),\n        \
+             Entry::of::<day11::Day11>(),\n    ]\n}\n"
+        );
+        assert!(register(lib, 2021, 2).is_err());
+        assert!(register("pub mod utils;\n", 2021, 2).is_err());
+
+        // The days of another calendar are in a module of their own
+        let lib = register(lib, 2022, 1).unwrap();
+        assert_eq!(
+            lib,
+            "pub mod utils;\n\npub mod day1;\npub mod day10;\npub mod day2;\npub mod y2022;\n\n\
+             fn registry() {\n    vec![\n        Entry::of::<day1::Day1>(),\n        \
+             Entry::of::<day2::Day2>(),\n        Entry::of::<day10::Day10>(),\n        \
+             Entry::of::<y2022::day1::Day1>(),\n    ]\n}\n"
+        );
+        let lib = register(&lib, 2022, 3).unwrap();
+        assert!(lib.ends_with(
+            "Entry::of::<y2022::day1::Day1>(),\n        Entry::of::<y2022::day3::Day3>(),\n    ]\n}\n"
+        ));
+        assert_eq!(lib.matches("pub mod y2022;").count(), 1);
+        assert!(register(&lib, 2022, 1).is_err());
+        assert!(register(&lib, 2021, 1).is_err());
+
+        let module =
+            add_module("//! Days of 2022\n\npub mod day1;\npub mod day3;\n", "day2").unwrap();
+        assert_eq!(
+            module,
+            "//! Days of 2022\n\npub mod day1;\npub mod day2;\npub mod day3;\n"
+        );
+        assert!(add_module(&module, "day3").is_err());
+    }
+}
@@ -0,0 +1,370 @@
+use std::fmt::Display;
+
+use color_eyre::eyre;
+
+use crate::{
+    harness::{time_func, Allocations, Outcome, Timings},
+    memory,
+    params::AnyParam,
+    picture, trace, EyreResult, Part,
+};
+
+/// Calendar of the solutions that don't set [`Solution::YEAR`]
+pub const DEFAULT_YEAR: usize = 2021;
+
+/// The solution of a puzzle, from the parsing of the input to the answers of each part
+pub trait Solution {
+    /// Year of the calendar the puzzle is from
+    const YEAR: usize = DEFAULT_YEAR;
+    const DAY: usize;
+    const TITLE: &'static str;
+    /// Parts that are solved, a day without a second part only lists [`Part::Part1`]
+    const PARTS: &'static [Part] = &[Part::Part1, Part::Part2];
+    /// Sentences presenting the answer of each part, the `{}` is replaced by the answer and
+    /// `{name}` by the value of the parameter `name`
+    const MESSAGES: [&'static str; 2] = ["{}", "{}"];
+    /// Constants of the puzzle that can be changed when running the solution
+    const PARAMS: &'static [&'static dyn AnyParam] = &[];
+    /// Whether [`Solution::shared`] does some work, in which case it is timed separately
+    const SHARED_STEP: bool = false;
+
+    type Parsed<'i>: Clone;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn parse(input: &str) -> EyreResult<Self::Parsed<'_>>;
+
+    /// Work needed by all the parts, done once on the parsed input
+    fn shared(parsed: Self::Parsed<'_>) -> EyreResult<Self::Parsed<'_>> {
+        Ok(parsed)
+    }
+
+    fn part1(parsed: Self::Parsed<'_>) -> EyreResult<Self::Answer1>;
+
+    fn part2(_parsed: Self::Parsed<'_>) -> EyreResult<Self::Answer2> {
+        eyre::bail!("part 2 is not implemented")
+    }
+}
+
+/// Implements [`Solution::parse`], [`Solution::part1`] and [`Solution::part2`] in an
+/// `impl Solution` block with the `parser`, `part1` and `part2` functions of the module. Only the
+/// listed ones are implemented when some are given, such as `forward_solution!(part1, part2)`
+#[macro_export]
+macro_rules! forward_solution {
+    () => {
+        $crate::forward_solution!(parse, part1, part2);
+    };
+    (@ parse) => {
+        fn parse(input: &str) -> $crate::EyreResult<Self::Parsed<'_>> {
+            parser(input)
+        }
+    };
+    (@ part1) => {
+        fn part1(parsed: Self::Parsed<'_>) -> $crate::EyreResult<Self::Answer1> {
+            part1(parsed)
+        }
+    };
+    (@ part2) => {
+        fn part2(parsed: Self::Parsed<'_>) -> $crate::EyreResult<Self::Answer2> {
+            part2(parsed)
+        }
+    };
+    ($($function:ident),+ $(,)?) => {
+        $($crate::forward_solution!(@ $function);)+
+    };
+}
+
+type Prepared<'i, S> = (<S as Solution>::Parsed<'i>, Timings, Option<Allocations>);
+
+fn prepare<S: Solution>(input: &str) -> EyreResult<Prepared<'_, S>> {
+    let ((parsed, parse), parse_alloc) = trace::context(format!("day {} parse", S::DAY), || {
+        memory::track(|| time_func(S::parse, input))
+    });
+    let parsed = parsed?;
+    let ((shared, inter), inter_alloc) = trace::context(format!("day {} shared", S::DAY), || {
+        memory::track(|| time_func(S::shared, parsed))
+    });
+    let timings = Timings {
+        parse: Some(parse),
+        inter: if S::SHARED_STEP { Some(inter) } else { None },
+        part: Default::default(),
+    };
+    let allocations = parse_alloc.map(|parse| Allocations {
+        parse: Some(parse),
+        inter: inter_alloc.filter(|_| S::SHARED_STEP),
+        part: Default::default(),
+    });
+    Ok((shared?, timings, allocations))
+}
+
+fn solve<S: Solution>(
+    part: Part,
+    parsed: S::Parsed<'_>,
+    mut timings: Timings,
+    mut allocations: Option<Allocations>,
+) -> EyreResult<Outcome> {
+    let context = format!("day {} part {}", S::DAY, part);
+    let (((answer, part_time), part_alloc), picture) = trace::context(context, || {
+        picture::capture(|| {
+            memory::track(|| match part {
+                Part::Part1 => {
+                    let (answer, time) = time_func(S::part1, parsed);
+                    (answer.map(|answer| answer.to_string()), time)
+                }
+                Part::Part2 => {
+                    let (answer, time) = time_func(S::part2, parsed);
+                    (answer.map(|answer| answer.to_string()), time)
+                }
+            })
+        })
+    });
+    let answer = answer?;
+    timings.part = part_time;
+    if let (Some(allocations), Some(part)) = (&mut allocations, part_alloc) {
+        allocations.part = part;
+    }
+
+    let template = match part {
+        Part::Part1 => S::MESSAGES[0],
+        Part::Part2 => S::MESSAGES[1],
+    };
+    let mut message = template.replacen("{}", &answer, 1);
+    for param in S::PARAMS {
+        message = message.replace(&format!("{{{}}}", param.name()), &param.value(part)?);
+    }
+    Ok(Outcome {
+        message,
+        answer,
+        timings,
+        allocations,
+        picture,
+    })
+}
+
+fn run<S: Solution>(part: Part, input: &str) -> EyreResult<Outcome> {
+    if !S::PARTS.contains(&part) {
+        eyre::bail!("part {} is not implemented", part);
+    }
+    let (parsed, timings, allocations) = prepare::<S>(input)?;
+    solve::<S>(part, parsed, timings, allocations)
+}
+
+/// Runs all the parts on a single parse of the input, the parsed input is cloned for all but the
+/// last part. Only the first outcome carries the parse and shared step timings.
+fn run_all_parts<S: Solution>(input: &str) -> EyreResult<Vec<EyreResult<Outcome>>> {
+    let (parsed, mut timings, mut allocations) = prepare::<S>(input)?;
+
+    let mut parsed = Some(parsed);
+    let mut outcomes = Vec::with_capacity(S::PARTS.len());
+    for (idx, &part) in S::PARTS.iter().enumerate() {
+        let input = if idx + 1 == S::PARTS.len() {
+            parsed.take()
+        } else {
+            parsed.clone()
+        };
+        outcomes.push(solve::<S>(part, input.unwrap(), timings, allocations));
+        timings.parse = None;
+        timings.inter = None;
+        if let Some(allocations) = &mut allocations {
+            allocations.parse = None;
+            allocations.inter = None;
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// Type erased handle on a [`Solution`], as stored in the [`Registry`]
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub year: usize,
+    pub day: usize,
+    pub title: &'static str,
+    pub parts: &'static [Part],
+    pub params: &'static [&'static dyn AnyParam],
+    run: fn(Part, &str) -> EyreResult<Outcome>,
+    run_all_parts: fn(&str) -> EyreResult<Vec<EyreResult<Outcome>>>,
+}
+
+impl Entry {
+    pub fn of<S: Solution>() -> Self {
+        Self {
+            year: S::YEAR,
+            day: S::DAY,
+            title: S::TITLE,
+            parts: S::PARTS,
+            params: S::PARAMS,
+            run: run::<S>,
+            run_all_parts: run_all_parts::<S>,
+        }
+    }
+
+    pub fn run(&self, part: Part, input: &str) -> EyreResult<Outcome> {
+        (self.run)(part, input)
+    }
+
+    pub fn run_all_parts(&self, input: &str) -> EyreResult<Vec<EyreResult<Outcome>>> {
+        (self.run_all_parts)(input)
+    }
+
+    pub fn latest_part(&self) -> Part {
+        *self.parts.last().expect("a solution has at least one part")
+    }
+}
+
+/// All the known solutions, ordered by year and day
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn new(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by_key(|entry| (entry.year, entry.day));
+        Self { entries }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, year: usize, day: usize) -> EyreResult<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.year == year && entry.day == day)
+            .ok_or_else(|| eyre::eyre!("Day {} of {} was not implemented", day, year))
+    }
+
+    /// Solutions of a single calendar
+    pub fn year(&self, year: usize) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(move |entry| entry.year == year)
+    }
+
+    pub fn latest(&self, year: usize) -> EyreResult<&Entry> {
+        self.year(year)
+            .last()
+            .ok_or_else(|| eyre::eyre!("No day is implemented for {}", year))
+    }
+
+    /// Most recent calendar with solutions that is not after `year`
+    pub fn latest_year(&self, year: usize) -> Option<usize> {
+        self.entries
+            .iter()
+            .rev()
+            .map(|entry| entry.year)
+            .find(|&y| y <= year)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, Registry, Solution};
+    use crate::{EyreResult, Part};
+
+    struct Numbers;
+
+    impl Solution for Numbers {
+        const DAY: usize = 1;
+        const TITLE: &'static str = "Numbers";
+        const MESSAGES: [&'static str; 2] = ["Sum is {}", "Product is {}"];
+
+        type Parsed<'i> = Vec<u64>;
+        type Answer1 = u64;
+        type Answer2 = u64;
+
+        fn parse(input: &str) -> EyreResult<Self::Parsed<'_>> {
+            input
+                .split_whitespace()
+                .map(|n| n.parse().map_err(Into::into))
+                .collect()
+        }
+
+        fn part1(numbers: Self::Parsed<'_>) -> EyreResult<u64> {
+            Ok(numbers.iter().sum())
+        }
+
+        fn part2(numbers: Self::Parsed<'_>) -> EyreResult<u64> {
+            Ok(numbers.iter().product())
+        }
+    }
+
+    struct Words;
+
+    impl Solution for Words {
+        const DAY: usize = 2;
+        const TITLE: &'static str = "Words";
+        const PARTS: &'static [Part] = &[Part::Part1];
+
+        type Parsed<'i> = Vec<&'i str>;
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> EyreResult<Self::Parsed<'_>> {
+            Ok(input.split_whitespace().collect())
+        }
+
+        fn part1(words: Self::Parsed<'_>) -> EyreResult<usize> {
+            Ok(words.len())
+        }
+    }
+
+    struct Later;
+
+    impl Solution for Later {
+        const YEAR: usize = 2022;
+        const DAY: usize = 1;
+        const TITLE: &'static str = "Later words";
+        const PARTS: &'static [Part] = &[Part::Part1];
+
+        type Parsed<'i> = <Words as Solution>::Parsed<'i>;
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> EyreResult<Self::Parsed<'_>> {
+            Words::parse(input)
+        }
+
+        fn part1(words: Self::Parsed<'_>) -> EyreResult<usize> {
+            Words::part1(words)
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let registry = Registry::new(vec![
+            Entry::of::<Words>(),
+            Entry::of::<Numbers>(),
+            Entry::of::<Later>(),
+        ]);
+        assert_eq!(registry.latest(2021).unwrap().day, 2);
+        assert_eq!(registry.latest(2022).unwrap().day, 1);
+        assert!(registry.latest(2020).is_err());
+        assert_eq!(registry.latest_year(2021), Some(2021));
+        assert_eq!(registry.latest_year(2025), Some(2022));
+        assert_eq!(registry.latest_year(2020), None);
+
+        let numbers = registry.get(2021, 1).unwrap();
+        assert_eq!(numbers.latest_part(), Part::Part2);
+        let outcome = numbers.run(Part::Part1, "2 3 4").unwrap();
+        assert_eq!(outcome.answer, "9");
+        assert_eq!(outcome.message, "Sum is 9");
+
+        let outcomes: Vec<_> = numbers
+            .run_all_parts("2 3 4")
+            .unwrap()
+            .into_iter()
+            .map(|outcome| outcome.unwrap())
+            .collect();
+        assert_eq!(outcomes[1].answer, "24");
+        assert!(outcomes[0].timings.parse.is_some());
+        assert!(outcomes[1].timings.parse.is_none());
+
+        let later = registry.get(2022, 1).unwrap();
+        assert_eq!(later.run(Part::Part1, "a b").unwrap().answer, "2");
+
+        let words = registry.get(2021, 2).unwrap();
+        assert_eq!(words.run(Part::Part1, "a b c").unwrap().answer, "3");
+        assert!(words.run(Part::Part2, "a b c").is_err());
+        assert!(registry.get(2021, 3).is_err());
+        assert!(registry.get(2022, 2).is_err());
+    }
+}
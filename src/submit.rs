@@ -0,0 +1,316 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use color_eyre::eyre::{self, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    download::{self, Downloader},
+    EyreResult, Part,
+};
+
+/// Verdict of the website on a submitted answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wrong,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Correct => write!(f, "correct"),
+            Verdict::TooHigh => write!(f, "too high"),
+            Verdict::TooLow => write!(f, "too low"),
+            Verdict::Wrong => write!(f, "wrong"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Response {
+    Verdict(Verdict),
+    /// An answer was submitted too recently, with the time left to wait if the page gave it
+    RateLimited(Option<Duration>),
+    /// The part is already solved, or is not unlocked yet
+    WrongLevel,
+}
+
+/// Text of the `<article>` of the page, where the website puts its messages
+fn article_text(html: &str) -> String {
+    let article = match (html.find("<article"), html.find("</article>")) {
+        (Some(start), Some(end)) if start < end => &html[start..end],
+        _ => html,
+    };
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in article.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub(crate) fn parse_response(html: &str) -> EyreResult<Response> {
+    let text = article_text(html);
+    if text.contains("That's the right answer") {
+        Ok(Response::Verdict(Verdict::Correct))
+    } else if text.contains("That's not the right answer") {
+        Ok(Response::Verdict(if text.contains("answer is too high") {
+            Verdict::TooHigh
+        } else if text.contains("answer is too low") {
+            Verdict::TooLow
+        } else {
+            Verdict::Wrong
+        }))
+    } else if text.contains("You gave an answer too recently") {
+        let wait = text
+            .split_once("You have ")
+            .and_then(|(_, rest)| rest.split_once(" left to wait"))
+            .and_then(|(wait, _)| humantime::parse_duration(wait).ok());
+        Ok(Response::RateLimited(wait))
+    } else if text.contains("You don't seem to be solving the right level") {
+        Ok(Response::WrongLevel)
+    } else {
+        eyre::bail!("Could not understand the response: {}", text)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Guess {
+    answer: String,
+    verdict: Verdict,
+    submitted_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PartLog {
+    correct: Option<String>,
+    guesses: Vec<Guess>,
+}
+
+impl PartLog {
+    /// Bounds on the answer given by the previous guesses, as (highest too low, lowest too high)
+    fn bounds(&self) -> (Option<i128>, Option<i128>) {
+        let numbers = |verdict| {
+            self.guesses
+                .iter()
+                .filter(move |guess| guess.verdict == verdict)
+                .filter_map(|guess| guess.answer.parse::<i128>().ok())
+        };
+        (
+            numbers(Verdict::TooLow).max(),
+            numbers(Verdict::TooHigh).min(),
+        )
+    }
+
+    /// Why `answer` is known to be wrong without submitting it, if it is
+    fn refusal(&self, answer: &str) -> Option<String> {
+        if let Some(guess) = self.guesses.iter().find(|guess| guess.answer == answer) {
+            return Some(format!(
+                "{} was already submitted and was {}",
+                answer, guess.verdict
+            ));
+        }
+
+        let value: i128 = answer.parse().ok()?;
+        match self.bounds() {
+            (Some(low), _) if value <= low => Some(format!(
+                "{} is not above {}, which was too low",
+                answer, low
+            )),
+            (_, Some(high)) if value >= high => Some(format!(
+                "{} is not below {}, which was too high",
+                answer, high
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Submitted answers of a day, stored as `<dir>/<year>/day<N>.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct DayLog {
+    part1: PartLog,
+    part2: PartLog,
+}
+
+impl DayLog {
+    pub(crate) fn path(dir: &Path, year: usize, day: usize) -> PathBuf {
+        let mut path = dir.to_path_buf();
+        path.push(year.to_string());
+        path.push(format!("day{}.json", day));
+        path
+    }
+
+    pub(crate) fn load(path: &Path) -> EyreResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read the submissions at {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Malformed submissions at {:?}", path))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> EyreResult<()> {
+        download::write_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    fn part(&mut self, part: Part) -> &mut PartLog {
+        match part {
+            Part::Part1 => &mut self.part1,
+            Part::Part2 => &mut self.part2,
+        }
+    }
+
+    fn record(&mut self, part: Part, answer: &str, verdict: Verdict) {
+        let log = self.part(part);
+        if verdict == Verdict::Correct {
+            log.correct = Some(answer.to_string());
+        }
+        log.guesses.push(Guess {
+            answer: answer.to_string(),
+            verdict,
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+/// Submits an answer unless it is known to be wrong, keeping track of the verdict in `log_dir`
+pub(crate) fn submit(
+    client: &Downloader,
+    log_dir: &Path,
+    year: usize,
+    day: usize,
+    part: Part,
+    answer: &str,
+) -> EyreResult<Response> {
+    let answer = answer.trim();
+    if answer.is_empty() || answer.contains('\n') {
+        eyre::bail!(
+            "The answer must be a single non empty line, got {:?}",
+            answer
+        );
+    }
+
+    let path = DayLog::path(log_dir, year, day);
+    let mut log = DayLog::load(&path)?;
+    if let Some(correct) = &log.part(part).correct {
+        if correct == answer {
+            return Ok(Response::Verdict(Verdict::Correct));
+        }
+        eyre::bail!(
+            "Part {} of day {} was already solved with {}",
+            part,
+            day,
+            correct
+        );
+    }
+    if let Some(reason) = log.part(part).refusal(answer) {
+        eyre::bail!("Refusing to submit: {}", reason);
+    }
+
+    let level = part.to_string();
+    let page = client.post_form(
+        &format!("/{}/day/{}/answer", year, day),
+        &[("level", &level), ("answer", answer)],
+    )?;
+    let response = parse_response(&page)?;
+    if let Response::Verdict(verdict) = response {
+        log.record(part, answer, verdict);
+        log.save(&path)?;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{parse_response, submit, DayLog, Response, Verdict};
+    use crate::{download::test::serve, download::Downloader, Part};
+
+    fn page(message: &str) -> String {
+        format!(
+            "<html><body><main><article><p>{}</p></article></main></body></html>",
+            message
+        )
+    }
+
+    #[test]
+    fn responses() {
+        let parse = |message: &str| parse_response(&page(message)).unwrap();
+        assert_eq!(
+            parse("That's the right answer! You are <em>one gold star</em> closer."),
+            Response::Verdict(Verdict::Correct)
+        );
+        assert_eq!(
+            parse("That's not the right answer; your answer is too high.  If you're stuck..."),
+            Response::Verdict(Verdict::TooHigh)
+        );
+        assert_eq!(
+            parse("That's not the right answer.  If you're stuck, make sure..."),
+            Response::Verdict(Verdict::Wrong)
+        );
+        assert_eq!(
+            parse("You gave an answer too recently. You have 4m 3s left to wait."),
+            Response::RateLimited(Some(Duration::from_secs(243)))
+        );
+        assert_eq!(
+            parse("You don't seem to be solving the right level.  Did you already complete it?"),
+            Response::WrongLevel
+        );
+        assert!(parse_response(&page("Something else")).is_err());
+    }
+
+    #[test]
+    fn refuse_known_wrong() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("aoc-submit-test-{}", std::process::id()));
+        let (url, server) = serve(vec![
+            (
+                200,
+                page("That's not the right answer; your answer is too high."),
+            ),
+            (
+                200,
+                page("That's not the right answer; your answer is too low."),
+            ),
+            (200, page("That's the right answer!")),
+        ]);
+        let client = Downloader::new(&url, Some("cookie")).unwrap();
+        let submit = |answer| submit(&client, &dir, 2021, 3, Part::Part1, answer);
+
+        assert_eq!(submit("100").unwrap(), Response::Verdict(Verdict::TooHigh));
+        assert!(submit("100").is_err());
+        assert!(submit("150").is_err());
+        assert_eq!(submit("10").unwrap(), Response::Verdict(Verdict::TooLow));
+        assert!(submit("5").is_err());
+        assert_eq!(submit("42").unwrap(), Response::Verdict(Verdict::Correct));
+        // Known to be correct, nothing is sent
+        assert_eq!(submit("42").unwrap(), Response::Verdict(Verdict::Correct));
+        assert!(submit("43").is_err());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /2021/day/3/answer "));
+        assert!(requests[0].ends_with("level=1&answer=100"));
+
+        let log = DayLog::load(&DayLog::path(&dir, 2021, 3)).unwrap();
+        assert_eq!(log.part1.guesses.len(), 3);
+        assert_eq!(log.part1.correct.as_deref(), Some("42"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Events explaining how the solutions reach their answers, written on stderr
+//!
+//! A run is given a verbosity with [`scope`], and the solutions record events with the
+//! [`trace!`](crate::trace!) macro, whose fields are only evaluated when the level of the event is
+//! shown.
+
+use std::{
+    cell::RefCell,
+    fmt::{Display, Write as _},
+};
+
+/// How detailed an event is, `-v` shows the [`Level::Info`] ones and `-vv` all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The main steps of a solution
+    Info = 1,
+    /// Each intermediate step, which can be numerous
+    Debug = 2,
+}
+
+struct Tracer {
+    verbosity: u8,
+    /// What is being run, prefixed to the events
+    context: String,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Tracer> = const {
+        RefCell::new(Tracer {
+            verbosity: 0,
+            context: String::new(),
+        })
+    };
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Events of the tests, which are not written on stderr
+    static CAPTURED: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Runs `f` with the events up to `verbosity` shown, none are when it is 0
+pub fn scope<O>(verbosity: u8, f: impl FnOnce() -> O) -> O {
+    let previous =
+        CURRENT.with(|current| std::mem::replace(&mut current.borrow_mut().verbosity, verbosity));
+    let out = f();
+    CURRENT.with(|current| current.borrow_mut().verbosity = previous);
+    out
+}
+
+/// Runs `f` with its events prefixed by `context`, such as `day 4 part 1`
+pub fn context<O>(context: impl Into<String>, f: impl FnOnce() -> O) -> O {
+    let context = context.into();
+    let previous =
+        CURRENT.with(|current| std::mem::replace(&mut current.borrow_mut().context, context));
+    let out = f();
+    CURRENT.with(|current| current.borrow_mut().context = previous);
+    out
+}
+
+/// Whether the events of `level` are shown
+pub fn enabled(level: Level) -> bool {
+    CURRENT.with(|current| current.borrow().verbosity >= level as u8)
+}
+
+/// The line of an event, as `[context] name key=value ...`
+fn format(context: &str, name: &str, fields: &[(&str, &dyn Display)]) -> String {
+    let mut line = String::new();
+    if !context.is_empty() {
+        line += &format!("[{}] ", context);
+    }
+    line += name;
+    for (key, value) in fields {
+        // Writing to a String can't fail
+        let _ = write!(line, " {}={}", key, value);
+    }
+    line.push('\n');
+    line
+}
+
+/// Writes an event on stderr, see [`trace!`](crate::trace!) to only build it when it is shown
+pub fn event(level: Level, name: &str, fields: &[(&str, &dyn Display)]) {
+    if !enabled(level) {
+        return;
+    }
+    let line = CURRENT.with(|current| format(&current.borrow().context, name, fields));
+    write(&line);
+}
+
+#[cfg(not(test))]
+fn write(line: &str) {
+    use std::io::Write;
+
+    // The trace is best effort, a closed stderr must not fail the run
+    let _ = std::io::stderr().lock().write_all(line.as_bytes());
+}
+
+#[cfg(test)]
+fn write(line: &str) {
+    CAPTURED.with(|captured| captured.borrow_mut().push_str(line));
+}
+
+/// Takes the events written so far by the current thread
+#[cfg(test)]
+pub(crate) fn captured() -> String {
+    CAPTURED.with(|captured| std::mem::take(&mut *captured.borrow_mut()))
+}
+
+/// Records an event of a level with named fields, such as
+/// `trace!(Info, "win", board = idx, score = score)`
+#[macro_export]
+macro_rules! trace {
+    ($level:ident, $name:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($crate::trace::Level::$level) {
+            $crate::trace::event(
+                $crate::trace::Level::$level,
+                $name,
+                &[$((stringify!($key), &$value as &dyn ::std::fmt::Display)),*],
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::{captured, context, enabled, format, scope, Level};
+
+    #[test]
+    fn levels() {
+        assert_eq!(
+            format("day 4 part 1", "win", &[("board", &2), ("score", &"4512")]),
+            "[day 4 part 1] win board=2 score=4512\n"
+        );
+        assert_eq!(format("", "draw", &[]), "draw\n");
+
+        let evaluated = Cell::new(0);
+        let value = || {
+            evaluated.set(evaluated.get() + 1);
+            0
+        };
+        crate::trace!(Info, "hidden", value = value());
+        assert!(!enabled(Level::Info));
+        scope(1, || {
+            assert!(enabled(Level::Info) && !enabled(Level::Debug));
+            context("day 1", || crate::trace!(Debug, "hidden", value = value()));
+        });
+        assert_eq!(evaluated.get(), 0);
+        assert_eq!(captured(), "");
+        scope(2, || {
+            context("day 1", || crate::trace!(Debug, "shown", value = value()))
+        });
+        assert_eq!(evaluated.get(), 1);
+        assert_eq!(captured(), "[day 1] shown value=0\n");
+    }
+}
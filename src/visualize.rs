@@ -0,0 +1,215 @@
+//! Animation of the state of the solutions in the terminal
+//!
+//! A run is given [`Settings`] with [`scope`], and the solutions draw their state with [`show`],
+//! which does nothing outside of a visualized run.
+
+use std::{
+    cell::RefCell,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use ansi_term::{Colour, Style};
+
+/// How the frames are drawn
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    /// Time each frame stays on screen
+    pub delay: Duration,
+    pub color: bool,
+    /// Draw each frame over the previous one instead of one after the other
+    pub in_place: bool,
+}
+
+struct Visualizer {
+    settings: Settings,
+    /// When the last frame was drawn
+    last: Option<Instant>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Visualizer>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the frames drawn according to `settings`, none are drawn when it is `None`
+pub fn scope<O>(settings: Option<Settings>, f: impl FnOnce() -> O) -> O {
+    let visualizer = settings.map(|settings| Visualizer {
+        settings,
+        last: None,
+    });
+    let previous = CURRENT.with(|current| current.replace(visualizer));
+    let out = f();
+    CURRENT.with(|current| current.replace(previous));
+    out
+}
+
+/// Whether the frames are drawn, to skip the work only needed by them
+pub fn enabled() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// Draws the frame built by `frame` on stderr, then waits until the next frame is due
+pub fn show(frame: impl FnOnce() -> Frame) {
+    let current = CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map(|visualizer| (visualizer.settings, visualizer.last))
+    });
+    let (settings, last) = match current {
+        None => return,
+        Some(current) => current,
+    };
+
+    let frame = frame().render(settings.color);
+    let mut out = String::new();
+    if settings.in_place {
+        if last.is_none() {
+            out += "\x1b[2J";
+        }
+        out += "\x1b[H";
+        for line in frame.lines() {
+            // Clears what remains of the previous frame on the line
+            out += line;
+            out += "\x1b[K\n";
+        }
+        out += "\x1b[J";
+    } else {
+        out += &frame;
+        out.push('\n');
+    }
+
+    if let Some(last) = last {
+        std::thread::sleep(settings.delay.saturating_sub(last.elapsed()));
+    }
+    // The visualization is best effort, a closed stderr must not fail the run
+    let _ = std::io::stderr().lock().write_all(out.as_bytes());
+    CURRENT.with(|current| {
+        if let Some(visualizer) = &mut *current.borrow_mut() {
+            visualizer.last = Some(Instant::now());
+        }
+    });
+}
+
+/// Colour of a palette, to tell apart the elements of a frame
+pub fn palette(index: usize) -> Colour {
+    const COLOURS: [u8; 12] = [196, 46, 21, 226, 201, 51, 208, 118, 93, 220, 39, 160];
+    Colour::Fixed(COLOURS[index % COLOURS.len()])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub style: Style,
+}
+
+impl Cell {
+    pub fn new(glyph: char) -> Self {
+        Self::styled(glyph, Style::new())
+    }
+
+    pub fn styled(glyph: char, style: Style) -> Self {
+        Self { glyph, style }
+    }
+}
+
+impl From<char> for Cell {
+    fn from(glyph: char) -> Self {
+        Self::new(glyph)
+    }
+}
+
+/// A grid of cells, followed by an optional caption
+pub struct Frame {
+    width: usize,
+    cells: Vec<Cell>,
+    caption: Option<String>,
+}
+
+impl Frame {
+    /// A blank frame
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            cells: vec![Cell::new(' '); width * height],
+            caption: None,
+        }
+    }
+
+    /// A frame showing `text`, as wide as its longest line
+    pub fn from_text(text: &str) -> Self {
+        let width = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut frame = Self::new(width, text.lines().count());
+        for (y, line) in text.lines().enumerate() {
+            for (x, glyph) in line.chars().enumerate() {
+                frame.set(x, y, glyph);
+            }
+        }
+        frame
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: impl Into<Cell>) {
+        self.cells[y * self.width + x] = cell.into();
+    }
+
+    pub fn caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// The lines of the frame, the cells sharing a style are painted together
+    pub fn render(&self, color: bool) -> String {
+        let mut out = String::new();
+        for row in self.cells.chunks(self.width.max(1)) {
+            if !color {
+                out.extend(row.iter().map(|cell| cell.glyph));
+            } else {
+                for run in row.chunk_by(|a, b| a.style == b.style) {
+                    let text: String = run.iter().map(|cell| cell.glyph).collect();
+                    out += &run[0].style.paint(text).to_string();
+                }
+            }
+            out.push('\n');
+        }
+        if let Some(caption) = &self.caption {
+            out += caption;
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ansi_term::Colour;
+
+    use super::{Cell, Frame};
+
+    #[test]
+    fn render() {
+        let mut frame = Frame::from_text("#..\n.#");
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert_eq!(frame.get(2, 1), Cell::new(' '));
+
+        frame.set(1, 0, Cell::styled('.', Colour::Red.normal()));
+        frame.set(2, 0, Cell::styled('.', Colour::Red.normal()));
+        let frame = frame.caption("Step 1");
+        assert_eq!(frame.render(false), "#..\n.# \nStep 1\n");
+        assert_eq!(
+            frame.render(true),
+            format!("#{}\n.# \nStep 1\n", Colour::Red.paint(".."))
+        );
+    }
+}
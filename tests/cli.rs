@@ -0,0 +1,44 @@
+//! Runs the `aoc` binary as a user would
+
+use std::process::{Command, Output};
+
+fn aoc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .args(["--no-history", "--no-timings"])
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn bench() {
+    let out = aoc(&[
+        "--bench",
+        "2",
+        "--warmup",
+        "0",
+        "-d",
+        "3",
+        "-i",
+        "examples/day3",
+    ]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{}", stderr);
+    assert!(stderr.contains("Benchmarking day 3 part"));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.lines().next().unwrap().contains("Median"));
+
+    let out = aoc(&[
+        "--bench",
+        "2",
+        "--jobs",
+        "2",
+        "-d",
+        "3",
+        "-i",
+        "examples/day3",
+    ]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--jobs can't be used with --bench"));
+}
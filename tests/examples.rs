@@ -0,0 +1,75 @@
+//! Runs every example of the `examples` directory that has expected answers, as one test each
+
+use std::{path::Path, time::Duration};
+
+use aoc::{
+    examples::{self, Example, Expected},
+    harness,
+    solution::{Entry, DEFAULT_YEAR},
+};
+use libtest_mimic::{Arguments, Failed, Trial};
+
+/// A solver stuck on an example fails its test instead of hanging the suite
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn check(entry: Entry, example: &Example, expected: &Expected) -> Result<(), Failed> {
+    let input = std::fs::read_to_string(&example.path)?;
+
+    let mut failures = Vec::new();
+    for &part in entry.parts {
+        let expected = match expected.get(part) {
+            None => continue,
+            Some(expected) => expected,
+        };
+        let input = input.clone();
+        match harness::with_timeout(Some(TIMEOUT), move || entry.run(part, &input)) {
+            Ok(outcome) if outcome.answer.trim_end() == expected => (),
+            Ok(outcome) => failures.push(format!(
+                "part {}: expected {}, got {}",
+                part, expected, outcome.answer
+            )),
+            Err(e) => failures.push(format!("part {}: {:#}", part, e)),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n").into())
+    }
+}
+
+fn main() -> aoc::EyreResult<()> {
+    let args = Arguments::from_args();
+
+    let registry = aoc::registry();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut years: Vec<_> = registry.iter().map(|entry| entry.year).collect();
+    years.dedup();
+
+    let mut trials = Vec::new();
+    for year in years {
+        let dir = examples::year_dir(&dir, year);
+        if !dir.exists() {
+            continue;
+        }
+        for example in examples::discover(&dir)? {
+            let entry = registry.get(year, example.day).ok().copied();
+            let expected = example.expected()?;
+            let name = match year {
+                DEFAULT_YEAR => example.name.clone(),
+                year => format!("{}/{}", year, example.name),
+            };
+            let trial = match (entry, expected) {
+                (Some(entry), Some(expected)) => {
+                    Trial::test(name, move || check(entry, &example, &expected))
+                }
+                // Examples without a solution or expected answers are listed as ignored
+                _ => Trial::test(name, || Ok(())).with_ignored_flag(true),
+            };
+            trials.push(trial);
+        }
+    }
+
+    libtest_mimic::run(&args, trials).exit()
+}